@@ -0,0 +1,102 @@
+//! Times list, find, search and save on a generated 100k-task file through `--rpc`,
+//! failing if any of them, loading aside, takes a second or more.
+//!
+//! Build the binary first, in the same profile:
+//!
+//!     cargo build --release && cargo run --release --example big_list
+
+use std::{
+    env,
+    io::{BufRead, BufReader, Write},
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use serde_json::json;
+
+const TASKS: usize = 100_000;
+const LIMIT: Duration = Duration::from_secs(1);
+
+fn main() {
+    let binary = env::current_exe()
+        .ok()
+        .and_then(|exe| Some(exe.parent()?.parent()?.join("learn")))
+        .filter(|binary| binary.exists())
+        .expect("build the learn binary in the same profile before running this example");
+
+    let dir = env::temp_dir().join(format!("todocli-big-list-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let filename = dir.join("tasks.json");
+
+    let priorities = ["Low", "Medium", "High"];
+    let tasks: Vec<_> = (0..TASKS)
+        .map(|i| {
+            json!({
+                "name": format!("Task {}", i),
+                "description": format!("Synthetic task number {} with some text", i),
+                "priority": priorities[i % 3],
+                "add_time": "2024-01-01T12:00:00+00:00",
+            })
+        })
+        .collect();
+    std::fs::write(&filename, serde_json::to_vec(&tasks).unwrap()).unwrap();
+
+    let start = Instant::now();
+    let mut child = Command::new(binary)
+        .arg("--rpc")
+        .arg("--file")
+        .arg(&filename)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+
+    // The first response waits for the file to load, so it isn't held to the limit.
+    let requests = [
+        (
+            "find",
+            json!({ "method": "find_task", "params": { "name": format!("Task {}", TASKS - 1) }, "id": 1 }),
+        ),
+        ("list", json!({ "method": "list_tasks", "id": 2 })),
+        (
+            "search",
+            json!({ "method": "search_tasks", "params": { "query": format!("number {} with", TASKS - 1) }, "id": 3 }),
+        ),
+        ("save", json!({ "method": "save", "id": 4 })),
+    ];
+
+    let mut response = String::new();
+    for (index, (label, request)) in requests.iter().enumerate() {
+        let start_request = Instant::now();
+        writeln!(stdin, "{}", request).unwrap();
+        stdin.flush().unwrap();
+        response.clear();
+        stdout.read_line(&mut response).unwrap();
+        assert!(
+            !response.contains("\"error\""),
+            "{} failed: {}",
+            label,
+            response
+        );
+        if index == 0 {
+            println!("load + {:<5} {:?}", label, start.elapsed());
+            continue;
+        }
+
+        let elapsed = start_request.elapsed();
+        println!("{:<12} {:?}", label, elapsed);
+        assert!(
+            elapsed < LIMIT,
+            "{} took {:?}, over {:?}",
+            label,
+            elapsed,
+            LIMIT
+        );
+    }
+
+    drop(stdin);
+    child.wait().unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+}
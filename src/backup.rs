@@ -0,0 +1,225 @@
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use chrono::{DateTime, Local};
+
+use crate::Task;
+
+pub const DEFAULT_MAX_BACKUPS: usize = 10;
+
+const BACKUP_DIR: &str = "backups";
+const BACKUP_SUFFIX: &str = ".backup-";
+
+pub struct BackupPolicy {
+    pub max_backups: usize,
+    /// Abort the save/load when the backup can't be written.
+    pub strict: bool,
+}
+
+impl Default for BackupPolicy {
+    fn default() -> Self {
+        Self {
+            max_backups: DEFAULT_MAX_BACKUPS,
+            strict: false,
+        }
+    }
+}
+
+pub struct BackupInfo {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: Option<DateTime<Local>>,
+}
+
+/// Copies `filename` into its backups directory, if it exists.
+pub fn backup_file(filename: &str, policy: &BackupPolicy) -> Result<Option<PathBuf>, String> {
+    if !Path::new(filename).exists() {
+        return Ok(None);
+    }
+
+    let target = new_backup_path(filename)?;
+    fs::copy(filename, &target)
+        .map_err(|err| format!("Error backing up \"{}\": {}", filename, err))?;
+    prune(filename, policy.max_backups)?;
+    Ok(Some(target))
+}
+
+/// Writes the in-memory `tasks` as a backup of `filename`.
+pub fn backup_tasks(
+    filename: &str,
+    tasks: &[Task],
+    policy: &BackupPolicy,
+) -> Result<PathBuf, String> {
+    let target = new_backup_path(filename)?;
+    let file = File::create(&target)
+        .map_err(|err| format!("Error creating backup \"{}\": {}", target.display(), err))?;
+    serde_json::to_writer(&file, tasks)
+        .map_err(|err| format!("Error writing backup \"{}\": {}", target.display(), err))?;
+    prune(filename, policy.max_backups)?;
+    Ok(target)
+}
+
+/// Lists the backups of `filename`, oldest first.
+pub fn list_backups(filename: &str) -> Result<Vec<BackupInfo>, String> {
+    let dir = backup_dir(filename);
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let prefix = format!("{}{}", file_name(filename), BACKUP_SUFFIX);
+    let entries = fs::read_dir(&dir)
+        .map_err(|err| format!("Error reading \"{}\": {}", dir.display(), err))?;
+
+    let mut backups: Vec<BackupInfo> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(&prefix))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            Some(BackupInfo {
+                path: entry.path(),
+                size: metadata.len(),
+                modified: metadata
+                    .modified()
+                    .ok()
+                    .map(|time: SystemTime| DateTime::<Local>::from(time)),
+            })
+        })
+        .collect();
+
+    backups.sort_by_cached_key(|backup| backup_order(&backup.path, &prefix));
+    Ok(backups)
+}
+
+/// Sort key for a backup: its timestamp, which sorts as text, then the counter that
+/// numbers backups taken within the same second.
+fn backup_order(path: &Path, prefix: &str) -> (String, u64) {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let suffix = name.strip_prefix(prefix).unwrap_or(&name);
+    // The timestamp holds one dash; a counter follows a second one.
+    match suffix.match_indices('-').nth(1) {
+        Some((at, _)) => (
+            suffix[..at].to_owned(),
+            suffix[at + 1..].parse().unwrap_or(0),
+        ),
+        None => (suffix.to_owned(), 0),
+    }
+}
+
+fn prune(filename: &str, max_backups: usize) -> Result<(), String> {
+    let backups = list_backups(filename)?;
+    let excess = backups.len().saturating_sub(max_backups);
+    for backup in &backups[..excess] {
+        fs::remove_file(&backup.path).map_err(|err| {
+            format!(
+                "Error removing old backup \"{}\": {}",
+                backup.path.display(),
+                err
+            )
+        })?;
+    }
+    Ok(())
+}
+
+fn new_backup_path(filename: &str) -> Result<PathBuf, String> {
+    let dir = backup_dir(filename);
+    fs::create_dir_all(&dir)
+        .map_err(|err| format!("Error creating \"{}\": {}", dir.display(), err))?;
+
+    let base = format!(
+        "{}{}{}",
+        file_name(filename),
+        BACKUP_SUFFIX,
+        Local::now().format("%Y%m%d-%H%M%S")
+    );
+
+    // Several backups can land in the same second, e.g. a load right after a save.
+    // They are numbered past the highest counter taken, as pruning frees the low ones.
+    let taken = fs::read_dir(&dir)
+        .map_err(|err| format!("Error reading \"{}\": {}", dir.display(), err))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            match name.strip_prefix(&base)? {
+                "" => Some(0),
+                rest => rest.strip_prefix('-')?.parse::<u64>().ok(),
+            }
+        })
+        .max();
+    Ok(match taken {
+        Some(counter) => dir.join(format!("{}-{}", base, counter + 1)),
+        None => dir.join(base),
+    })
+}
+
+fn backup_dir(filename: &str) -> PathBuf {
+    match Path::new(filename).parent() {
+        Some(parent) => parent.join(BACKUP_DIR),
+        None => PathBuf::from(BACKUP_DIR),
+    }
+}
+
+fn file_name(filename: &str) -> String {
+    match Path::new(filename).file_name() {
+        Some(name) => name.to_string_lossy().into_owned(),
+        None => filename.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_second_backups_sort_by_counter() {
+        let prefix = "tasks.json.backup-";
+        let mut names = vec![
+            "tasks.json.backup-20240102-030405-10",
+            "tasks.json.backup-20240102-030405-2",
+            "tasks.json.backup-20240102-030406",
+            "tasks.json.backup-20240102-030405",
+            "tasks.json.backup-20240102-030405-1",
+        ];
+        names.sort_by_cached_key(|name| backup_order(Path::new(name), prefix));
+        assert_eq!(
+            names,
+            [
+                "tasks.json.backup-20240102-030405",
+                "tasks.json.backup-20240102-030405-1",
+                "tasks.json.backup-20240102-030405-2",
+                "tasks.json.backup-20240102-030405-10",
+                "tasks.json.backup-20240102-030406",
+            ]
+        );
+    }
+
+    #[test]
+    fn prune_keeps_the_newest_backups() {
+        let dir = std::env::temp_dir().join(format!("todocli-backup-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let filename = dir.join("tasks.json").to_string_lossy().into_owned();
+        fs::write(&filename, "[]").unwrap();
+
+        let policy = BackupPolicy {
+            max_backups: 3,
+            strict: true,
+        };
+        let mut written = vec![];
+        for _ in 0..12 {
+            written.push(backup_file(&filename, &policy).unwrap().unwrap());
+        }
+
+        let kept: Vec<PathBuf> = list_backups(&filename)
+            .unwrap()
+            .into_iter()
+            .map(|backup| backup.path)
+            .collect();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(kept, written[written.len() - 3..]);
+    }
+}
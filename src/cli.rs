@@ -0,0 +1,57 @@
+use crate::{SaveMode, Status, Task, TasksManager};
+
+/// One-shot commands run from the shell instead of the menu.
+pub enum Action {
+    Add(Task),
+    List(Option<Status>),
+    Find(String),
+    Done(String),
+    Remove(String),
+}
+
+impl Action {
+    fn is_mutating(&self) -> bool {
+        matches!(self, Action::Add(_) | Action::Done(_) | Action::Remove(_))
+    }
+}
+
+/// Runs `action` against the loaded tasks, saving to `filename` when it changed them.
+///
+/// Results go to stdout; the error message is for the caller to report.
+pub fn run(
+    tasks_manager: &mut TasksManager,
+    filename: &str,
+    read_only: bool,
+    action: Action,
+) -> Result<(), String> {
+    if read_only && action.is_mutating() {
+        return Err("Read-only mode: this command would change the tasks".to_owned());
+    }
+
+    let msg = match action {
+        Action::Add(task) => tasks_manager.add_task(task)?,
+        Action::List(status) => {
+            tasks_manager.print_tasks(status);
+            return Ok(());
+        }
+        Action::Find(query) => {
+            let matches = match tasks_manager.lookup(&query) {
+                Some(index) => vec![index],
+                None => tasks_manager.search_tasks(&query),
+            };
+            if matches.is_empty() {
+                return Err(format!("No task matches \"{}\"", query));
+            }
+            for index in matches {
+                tasks_manager.tasks[index].print_task();
+            }
+            return Ok(());
+        }
+        Action::Done(name) => tasks_manager.mark_done(&name)?,
+        Action::Remove(name) => tasks_manager.remove_task(&name)?,
+    };
+
+    tasks_manager.store_to_file(filename, SaveMode::Overwrite)?;
+    println!("{}", msg);
+    Ok(())
+}
@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+
+use crate::Task;
+
+pub struct FieldChange {
+    pub field: &'static str,
+    pub before: String,
+    pub after: String,
+}
+
+pub struct EditedTask<'a> {
+    pub task: &'a Task,
+    pub changes: Vec<FieldChange>,
+}
+
+/// Differences between two task lists, matching tasks by id.
+pub struct TaskDiff<'a> {
+    pub added: Vec<&'a Task>,
+    pub removed: Vec<&'a Task>,
+    pub edited: Vec<EditedTask<'a>>,
+}
+
+impl TaskDiff<'_> {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.edited.is_empty()
+    }
+
+    /// One-line count summary, e.g. "3 added, 1 removed, 2 edited".
+    pub fn summary(&self) -> String {
+        format!(
+            "{} added, {} removed, {} edited",
+            self.added.len(),
+            self.removed.len(),
+            self.edited.len()
+        )
+    }
+}
+
+/// Compares `before` with `after`; a renamed task shows as edited.
+pub fn diff_tasks<'a>(before: &'a [Task], after: &'a [Task]) -> TaskDiff<'a> {
+    let before_by_id = by_id(before);
+    let after_by_id = by_id(after);
+
+    let mut added = vec![];
+    let mut edited = vec![];
+    for task in after {
+        match before_by_id.get(&task.id) {
+            Some(previous) if std::ptr::eq(after_by_id[&task.id], task) => {
+                let changes = field_changes(previous, task);
+                if !changes.is_empty() {
+                    edited.push(EditedTask { task, changes });
+                }
+            }
+            Some(_) => {}
+            None => added.push(task),
+        }
+    }
+
+    let removed = before
+        .iter()
+        .filter(|task| !after_by_id.contains_key(&task.id))
+        .collect();
+
+    TaskDiff {
+        added,
+        removed,
+        edited,
+    }
+}
+
+/// First task with each id; ids are unique once a list is loaded.
+fn by_id(tasks: &[Task]) -> HashMap<u64, &Task> {
+    let mut index = HashMap::with_capacity(tasks.len());
+    for task in tasks {
+        index.entry(task.id).or_insert(task);
+    }
+    index
+}
+
+fn field_changes(before: &Task, after: &Task) -> Vec<FieldChange> {
+    let mut changes = vec![];
+    let mut compare = |field, before: String, after: String| {
+        if before != after {
+            changes.push(FieldChange {
+                field,
+                before,
+                after,
+            });
+        }
+    };
+
+    compare("name", before.name.clone(), after.name.clone());
+    compare(
+        "description",
+        before.description.clone(),
+        after.description.clone(),
+    );
+    compare(
+        "priority",
+        before.priority.to_string(),
+        after.priority.to_string(),
+    );
+    compare(
+        "status",
+        before.status.to_string(),
+        after.status.to_string(),
+    );
+    compare(
+        "pomodoros",
+        before.pomodoros.to_string(),
+        after.pomodoros.to_string(),
+    );
+    compare(
+        "due",
+        before
+            .due_date
+            .map(|due| due.to_rfc3339())
+            .unwrap_or_default(),
+        after
+            .due_date
+            .map(|due| due.to_rfc3339())
+            .unwrap_or_default(),
+    );
+    compare("tags", before.tags.join(", "), after.tags.join(", "));
+    compare(
+        "added",
+        before.add_time.to_rfc3339(),
+        after.add_time.to_rfc3339(),
+    );
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Priority;
+
+    fn task(id: u64, name: &str) -> Task {
+        let mut task = Task::new(name.to_owned(), String::new(), Priority::Low);
+        task.id = id;
+        task
+    }
+
+    #[test]
+    fn unchanged_lists_have_no_diff() {
+        let before = vec![task(1, "milk"), task(2, "bread")];
+        let after = before.clone();
+        let diff = diff_tasks(&before, &after);
+        assert!(diff.is_empty());
+        assert_eq!(diff.summary(), "0 added, 0 removed, 0 edited");
+    }
+
+    #[test]
+    fn finds_added_and_removed_tasks() {
+        let before = vec![task(1, "milk"), task(2, "bread")];
+        let after = vec![before[1].clone(), task(3, "eggs")];
+
+        let diff = diff_tasks(&before, &after);
+        let names = |tasks: &[&Task]| -> Vec<String> {
+            tasks.iter().map(|task| task.name.clone()).collect()
+        };
+        assert_eq!(names(&diff.added), ["eggs"]);
+        assert_eq!(names(&diff.removed), ["milk"]);
+        assert!(diff.edited.is_empty());
+        assert_eq!(diff.summary(), "1 added, 1 removed, 0 edited");
+    }
+
+    #[test]
+    fn lists_each_edited_field() {
+        let before = vec![task(1, "milk"), task(2, "bread")];
+        let mut after = before.clone();
+        after[1].description = "rye".to_owned();
+        after[1].priority = Priority::High;
+        after[1].tags = vec!["shop".to_owned()];
+
+        let diff = diff_tasks(&before, &after);
+        assert_eq!(diff.edited.len(), 1);
+        assert_eq!(diff.edited[0].task.id, 2);
+        let changes: Vec<(&str, &str, &str)> = diff.edited[0]
+            .changes
+            .iter()
+            .map(|change| (change.field, change.before.as_str(), change.after.as_str()))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                ("description", "", "rye"),
+                ("priority", "Low", "High"),
+                ("tags", "", "shop"),
+            ]
+        );
+    }
+
+    #[test]
+    fn rename_is_a_field_change() {
+        let before = vec![task(1, "milk")];
+        let mut after = before.clone();
+        after[0].name = "oat milk".to_owned();
+
+        let diff = diff_tasks(&before, &after);
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+        assert_eq!(diff.edited.len(), 1);
+        let change = &diff.edited[0].changes[0];
+        assert_eq!(
+            (change.field, change.before.as_str(), change.after.as_str()),
+            ("name", "milk", "oat milk")
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use chrono::{DateTime, Local};
+
+use crate::Task;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Raw bytes per RFC 2047 encoded word, so each word stays within 75 characters.
+const ENCODED_WORD_BYTES: usize = 45;
+
+/// Builds an RFC 5322 message for one task, or a digest when several are given.
+pub fn compose(tasks: &[&Task], from: &str, to: &str, date: DateTime<Local>) -> String {
+    let subject = match tasks {
+        [task] => task.name.clone(),
+        _ => format!("Tasks digest ({} tasks)", tasks.len()),
+    };
+
+    let mut message = String::new();
+    message.push_str(&format!("From: {}\r\n", from));
+    message.push_str(&format!("To: {}\r\n", to));
+    message.push_str(&format!("Subject: {}\r\n", encode_header(&subject)));
+    message.push_str(&format!("Date: {}\r\n", date.to_rfc2822()));
+    message.push_str("MIME-Version: 1.0\r\n");
+    message.push_str("Content-Type: text/plain; charset=utf-8\r\n");
+    message.push_str("Content-Transfer-Encoding: 8bit\r\n");
+    message.push_str("\r\n");
+
+    let bodies: Vec<String> = tasks.iter().map(|task| task_body(task)).collect();
+    message.push_str(&bodies.join("\r\n"));
+
+    message
+}
+
+fn task_body(task: &Task) -> String {
+    let mut body = format!(
+        "{}\r\nPriority: {}\r\nAdded: {}\r\n",
+        task.name,
+        task.priority,
+        task.add_time.format("%d-%m-%Y %H:%M:%S")
+    );
+    if let Some(due_date) = task.due_date {
+        body.push_str(&format!("Due: {}\r\n", due_date.format("%d-%m-%Y %H:%M")));
+    }
+    if !task.description.is_empty() {
+        body.push_str("\r\n");
+        for line in task.description.lines() {
+            body.push_str(line);
+            body.push_str("\r\n");
+        }
+    }
+    body
+}
+
+/// Encodes a header value as RFC 2047 base64 words when it isn't plain ASCII.
+///
+/// Line breaks become spaces first, as a bare one would end the header.
+fn encode_header(value: &str) -> String {
+    let value = value
+        .split(['\r', '\n'])
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    if value.is_ascii() {
+        return value;
+    }
+
+    let mut words = vec![];
+    let mut chunk = String::new();
+    for c in value.chars() {
+        if chunk.len() + c.len_utf8() > ENCODED_WORD_BYTES {
+            words.push(encoded_word(&chunk));
+            chunk.clear();
+        }
+        chunk.push(c);
+    }
+    if !chunk.is_empty() {
+        words.push(encoded_word(&chunk));
+    }
+
+    words.join("\r\n ")
+}
+
+fn encoded_word(chunk: &str) -> String {
+    format!("=?UTF-8?B?{}?=", base64(chunk.as_bytes()))
+}
+
+fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for group in bytes.chunks(3) {
+        let b = [
+            group[0],
+            *group.get(1).unwrap_or(&0),
+            *group.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= group.len() {
+                encoded.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::Priority;
+
+    #[test]
+    fn base64_matches_rfc_4648_vectors() {
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (input, encoded) in vectors {
+            assert_eq!(base64(input.as_bytes()), encoded);
+        }
+        assert_eq!(base64(&[0xff, 0xfe, 0xfd]), "//79");
+    }
+
+    #[test]
+    fn encodes_non_ascii_headers_as_encoded_words() {
+        assert_eq!(encode_header("Buy milk"), "Buy milk");
+        assert_eq!(encode_header("Café"), "=?UTF-8?B?Q2Fmw6k=?=");
+
+        let long = "Задача ".repeat(10);
+        let encoded = encode_header(&long);
+        let words: Vec<&str> = encoded.split("\r\n ").collect();
+        assert!(words.len() > 1);
+        for word in words {
+            assert!(word.len() <= 75, "{} is too long", word);
+            assert!(word.starts_with("=?UTF-8?B?") && word.ends_with("?="));
+        }
+    }
+
+    #[test]
+    fn body_lists_the_due_date() {
+        let date = Local.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+        let mut task = Task::new("milk".to_owned(), "oat\nbarista".to_owned(), Priority::High);
+        task.add_time = date;
+        task.due_date = Some(Local.with_ymd_and_hms(2024, 1, 5, 18, 0, 0).unwrap());
+
+        assert_eq!(
+            task_body(&task),
+            "milk\r\nPriority: High\r\nAdded: 02-01-2024 03:04:05\r\nDue: 05-01-2024 18:00\r\n\r\noat\r\nbarista\r\n"
+        );
+        task.due_date = None;
+        assert!(!task_body(&task).contains("Due:"));
+    }
+
+    #[test]
+    fn line_breaks_cannot_end_a_header() {
+        assert_eq!(
+            encode_header("one\r\nBcc: x@example.com"),
+            "one Bcc: x@example.com"
+        );
+        assert_eq!(encode_header("a\n\nb\r"), "a b");
+
+        let task = Task::new(
+            "one\r\nBcc: x@example.com".to_owned(),
+            String::new(),
+            Priority::Low,
+        );
+        let date = Local.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+        let message = compose(&[&task], "a@example.com", "b@example.com", date);
+        let headers = message.split("\r\n\r\n").next().unwrap();
+        assert!(headers.lines().all(|line| !line.starts_with("Bcc:")));
+        assert!(headers.contains("Subject: one Bcc: x@example.com\r\n"));
+    }
+}
@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Local};
+
+use crate::{parse_due_date, parse_tags, Priority, Status, Task};
+
+pub enum ExportFormat {
+    Csv,
+    Markdown,
+}
+
+const CSV_HEADER: [&str; 7] = [
+    "name",
+    "description",
+    "priority",
+    "add_time",
+    "status",
+    "due_date",
+    "tags",
+];
+
+/// Renders one CSV row per task after a header row, quoting fields as RFC 4180 requires.
+pub fn to_csv(tasks: &[Task]) -> String {
+    let mut csv = CSV_HEADER.join(",");
+    csv.push('\n');
+    for task in tasks {
+        let fields = [
+            task.name.clone(),
+            task.description.clone(),
+            task.priority.to_string(),
+            task.add_time.to_rfc3339(),
+            task.status.to_string(),
+            task.due_date
+                .map(|due_date| due_date.to_rfc3339())
+                .unwrap_or_default(),
+            task.tags.join(","),
+        ];
+        let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// Splits CSV text into rows of fields; quoted fields may hold commas, quotes and newlines.
+pub fn parse_csv(text: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                c => field.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err("Unterminated quoted field".to_owned());
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// Maps CSV rows back into tasks by their header; only the name column is required.
+///
+/// A missing `add_time` becomes `now`, other missing columns take the task defaults.
+/// Both dates are read as RFC 3339, as exported.
+pub fn tasks_from_csv(text: &str, now: DateTime<Local>) -> Result<Vec<Task>, String> {
+    let mut rows = parse_csv(text)?.into_iter();
+    let header: HashMap<String, usize> = match rows.next() {
+        Some(header) => header
+            .into_iter()
+            .enumerate()
+            .map(|(column, name)| (name.trim().to_lowercase(), column))
+            .collect(),
+        None => return Ok(vec![]),
+    };
+    if !header.contains_key("name") {
+        return Err("CSV header has no \"name\" column".to_owned());
+    }
+
+    let mut tasks = vec![];
+    // Row 1 is the header.
+    for (line, row) in rows.enumerate().map(|(index, row)| (index + 2, row)) {
+        if row.iter().all(|field| field.is_empty()) {
+            continue;
+        }
+        let column = |name: &str| {
+            header
+                .get(name)
+                .and_then(|&column| row.get(column))
+                .map(String::as_str)
+                .unwrap_or("")
+        };
+        let row_error = |msg: String| format!("Row {}: {}", line, msg);
+
+        let name = column("name");
+        if name.trim().is_empty() {
+            return Err(row_error("task name must not be empty".to_owned()));
+        }
+        let priority = match column("priority") {
+            "" => Priority::Low,
+            priority => priority.parse().map_err(row_error)?,
+        };
+
+        let mut task = Task::new(name.to_owned(), column("description").to_owned(), priority);
+        task.add_time = match column("add_time") {
+            "" => now,
+            add_time => DateTime::parse_from_rfc3339(add_time)
+                .map(|add_time| add_time.with_timezone(&Local))
+                .map_err(|err| row_error(format!("invalid add_time \"{}\": {}", add_time, err)))?,
+        };
+        if !column("status").is_empty() {
+            task.status = column("status").parse().map_err(row_error)?;
+        }
+        // Exports write RFC 3339; hand-written files can use the console's formats.
+        task.due_date = match DateTime::parse_from_rfc3339(column("due_date")) {
+            Ok(due_date) => Some(due_date.with_timezone(&Local)),
+            Err(_) => parse_due_date(column("due_date")).map_err(row_error)?,
+        };
+        task.tags = parse_tags(column("tags"));
+        tasks.push(task);
+    }
+    Ok(tasks)
+}
+
+/// Renders the tasks as a Markdown table with a checkbox column for status.
+pub fn to_markdown(tasks: &[Task]) -> String {
+    let mut markdown =
+        String::from("| Done | Name | Priority | Added | Due | Tags | Description |\n");
+    markdown.push_str("| --- | --- | --- | --- | --- | --- | --- |\n");
+    for task in tasks {
+        let done = match task.status {
+            Status::Done => "[x]",
+            Status::Pending => "[ ]",
+        };
+        let due = task
+            .due_date
+            .map(|due_date| due_date.format("%d-%m-%Y %H:%M").to_string())
+            .unwrap_or_default();
+        markdown.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} | {} |\n",
+            done,
+            markdown_cell(&task.name),
+            task.priority,
+            task.add_time.format("%d-%m-%Y %H:%M"),
+            due,
+            markdown_cell(&task.tags.join(", ")),
+            markdown_cell(&task.description)
+        ));
+    }
+    markdown
+}
+
+/// Escapes a value for a table cell, where pipes end the cell and newlines the row.
+fn markdown_cell(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .lines()
+        .collect::<Vec<_>>()
+        .join("<br>")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn at(day: u32, hour: u32, minute: u32, second: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2024, 1, day, hour, minute, second)
+            .unwrap()
+    }
+
+    #[test]
+    fn round_trips_awkward_fields() {
+        let mut task = Task::new(
+            "call \"Bob\", then Ann".to_owned(),
+            "line one\nline two, with \"quotes\"\r\nline three".to_owned(),
+            Priority::High,
+        );
+        task.add_time = at(1, 9, 30, 15);
+        task.status = Status::Done;
+        task.due_date = Some(at(2, 17, 0, 59));
+        task.tags = vec!["home".to_owned(), "phone".to_owned()];
+        let plain = Task::new("plain".to_owned(), String::new(), Priority::Low);
+
+        let csv = to_csv(&[task, plain]);
+        let tasks = tasks_from_csv(&csv, at(9, 0, 0, 0)).unwrap();
+
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].name, "call \"Bob\", then Ann");
+        assert_eq!(
+            tasks[0].description,
+            "line one\nline two, with \"quotes\"\r\nline three"
+        );
+        assert!(tasks[0].priority == Priority::High);
+        assert!(tasks[0].status == Status::Done);
+        assert_eq!(tasks[0].add_time, at(1, 9, 30, 15));
+        assert_eq!(tasks[0].due_date, Some(at(2, 17, 0, 59)));
+        assert_eq!(tasks[0].tags, ["home", "phone"]);
+        assert_eq!(tasks[1].name, "plain");
+        assert_eq!(tasks[1].due_date, None);
+    }
+
+    #[test]
+    fn quotes_only_fields_that_need_it() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn parses_crlf_rows_and_quoted_newlines() {
+        let rows =
+            parse_csv("name,description\r\n\"a\",\"x\r\ny\"\r\nb,\"\"\"q\"\"\"\r\n").unwrap();
+        assert_eq!(
+            rows,
+            [
+                vec!["name", "description"],
+                vec!["a", "x\r\ny"],
+                vec!["b", "\"q\""],
+            ]
+        );
+        assert!(parse_csv("name\n\"open").is_err());
+    }
+
+    #[test]
+    fn missing_columns_take_defaults() {
+        let now = at(5, 12, 0, 0);
+        let tasks = tasks_from_csv("Name,Priority\r\nmilk,\r\n\r\nbread,medium", now).unwrap();
+
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].add_time, now);
+        assert!(tasks[0].priority == Priority::Low);
+        assert!(tasks[0].status == Status::Pending);
+        assert!(tasks[0].tags.is_empty());
+        assert!(tasks[1].priority == Priority::Medium);
+        assert!(tasks_from_csv("description\nx", now).is_err());
+        assert!(tasks_from_csv("name,priority\nmilk,urgent", now).is_err());
+    }
+}
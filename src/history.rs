@@ -0,0 +1,79 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+pub const MAX_ENTRIES: usize = 50;
+
+const HISTORY_FILE: &str = ".search_history";
+
+/// Search queries, most recent first, persisted one per line next to the data file.
+pub struct SearchHistory {
+    path: PathBuf,
+    entries: Vec<String>,
+}
+
+impl SearchHistory {
+    pub fn for_data_file(filename: &str) -> Self {
+        let path = match Path::new(filename).parent() {
+            Some(parent) => parent.join(HISTORY_FILE),
+            None => PathBuf::from(HISTORY_FILE),
+        };
+        let entries = Self::read_entries(&path);
+        Self { path, entries }
+    }
+
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// Looks up an entry by its 1-based number as listed.
+    pub fn get(&self, number: usize) -> Option<&str> {
+        number
+            .checked_sub(1)
+            .and_then(|index| self.entries.get(index))
+            .map(String::as_str)
+    }
+
+    /// Moves `query` to the front and saves.
+    ///
+    /// The file is re-read first so entries recorded by another instance are
+    /// kept, and replaced through a rename so readers never see a partial file.
+    pub fn record(&mut self, query: &str) -> Result<(), String> {
+        if query.is_empty() {
+            return Ok(());
+        }
+
+        let mut entries = vec![query.to_owned()];
+        for entry in Self::read_entries(&self.path)
+            .into_iter()
+            .chain(self.entries.drain(..))
+        {
+            if !entries.contains(&entry) {
+                entries.push(entry);
+            }
+        }
+        entries.truncate(MAX_ENTRIES);
+        self.entries = entries;
+
+        let temp = self
+            .path
+            .with_extension(format!("tmp-{}", std::process::id()));
+        let mut contents = self.entries.join("\n");
+        contents.push('\n');
+        fs::write(&temp, contents)
+            .and_then(|_| fs::rename(&temp, &self.path))
+            .map_err(|err| format!("Error saving search history: {}", err))
+    }
+
+    fn read_entries(path: &Path) -> Vec<String> {
+        match fs::read_to_string(path) {
+            Ok(contents) => contents
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(str::to_owned)
+                .collect(),
+            Err(_) => vec![],
+        }
+    }
+}
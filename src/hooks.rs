@@ -0,0 +1,257 @@
+use std::{
+    env,
+    io::{ErrorKind, Write},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{Task, TaskEvent};
+
+/// How long a hook may run before it is killed.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Shell commands run after a task is added, completed or removed.
+///
+/// Each gets the task as JSON on stdin and `TODO_EVENT`, `TODO_NAME` and
+/// `TODO_PRIORITY` in its environment. Its stdout is discarded, as it would
+/// otherwise mix with the RPC responses; stderr is passed through.
+pub struct Hooks {
+    on_add: Option<String>,
+    on_complete: Option<String>,
+    on_remove: Option<String>,
+    pub timeout: Duration,
+}
+
+impl Default for Hooks {
+    fn default() -> Self {
+        Self {
+            on_add: None,
+            on_complete: None,
+            on_remove: None,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+/// Hook name as shown to the user: `on_add`, `on_complete` or `on_remove`.
+pub fn hook_name(event: TaskEvent) -> &'static str {
+    match event {
+        TaskEvent::Added => "on_add",
+        TaskEvent::Completed => "on_complete",
+        TaskEvent::Removed => "on_remove",
+    }
+}
+
+/// Environment variable setting a hook no flag set, e.g. `TODO_HOOK_ON_ADD`.
+fn env_var(event: TaskEvent) -> String {
+    format!("TODO_HOOK_{}", hook_name(event).to_uppercase())
+}
+
+const EVENTS: [TaskEvent; 3] = [TaskEvent::Added, TaskEvent::Completed, TaskEvent::Removed];
+
+impl Hooks {
+    /// The event set by a `--on-add`, `--on-complete` or `--on-remove` flag.
+    pub fn flag_event(flag: &str) -> Option<TaskEvent> {
+        EVENTS
+            .into_iter()
+            .find(|&event| flag == format!("--{}", hook_name(event).replace('_', "-")))
+    }
+
+    pub fn set(&mut self, event: TaskEvent, command: String) {
+        *self.slot(event) = Some(command);
+    }
+
+    /// Fills the hooks no flag set from the environment.
+    pub fn with_env(mut self) -> Self {
+        for event in EVENTS {
+            let slot = self.slot(event);
+            if slot.is_none() {
+                *slot = env::var(env_var(event))
+                    .ok()
+                    .filter(|command| !command.trim().is_empty());
+            }
+        }
+        self
+    }
+
+    /// Hook names and commands, in event order, for the ones that are set.
+    pub fn configured(&self) -> Vec<(&'static str, &str)> {
+        EVENTS
+            .into_iter()
+            .filter_map(|event| Some((hook_name(event), self.command(event)?)))
+            .collect()
+    }
+
+    fn command(&self, event: TaskEvent) -> Option<&str> {
+        match event {
+            TaskEvent::Added => self.on_add.as_deref(),
+            TaskEvent::Completed => self.on_complete.as_deref(),
+            TaskEvent::Removed => self.on_remove.as_deref(),
+        }
+    }
+
+    fn slot(&mut self, event: TaskEvent) -> &mut Option<String> {
+        match event {
+            TaskEvent::Added => &mut self.on_add,
+            TaskEvent::Completed => &mut self.on_complete,
+            TaskEvent::Removed => &mut self.on_remove,
+        }
+    }
+
+    /// Runs the hook for `event`, if one is set, and waits for it up to the timeout.
+    pub fn run(&self, event: TaskEvent, task: &Task) -> Result<(), String> {
+        let Some(command) = self.command(event) else {
+            return Ok(());
+        };
+        let name = hook_name(event);
+        let json = serde_json::to_string(task)
+            .map_err(|err| format!("Error serializing task for hook {}: {}", name, err))?;
+
+        let mut child = shell(command)
+            .env("TODO_EVENT", name)
+            .env("TODO_NAME", &task.name)
+            .env("TODO_PRIORITY", task.priority.to_string())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()
+            .map_err(|err| format!("Error running hook {}: {}", name, err))?;
+
+        // Written from another thread so a hook that never reads stdin can't
+        // block us past the timeout; one that exits without reading is fine.
+        if let Some(mut stdin) = child.stdin.take() {
+            thread::spawn(move || match stdin.write_all(json.as_bytes()) {
+                Err(err) if err.kind() != ErrorKind::BrokenPipe => {
+                    eprintln!("Error writing to hook {}: {}", name, err)
+                }
+                _ => {}
+            });
+        }
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            match child.try_wait() {
+                Ok(Some(status)) if status.success() => return Ok(()),
+                Ok(Some(status)) => return Err(format!("Hook {} failed: {}", name, status)),
+                Ok(None) if Instant::now() >= deadline => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(format!(
+                        "Hook {} timed out after {}s and was killed",
+                        name,
+                        self.timeout.as_secs_f32()
+                    ));
+                }
+                Ok(None) => thread::sleep(POLL_INTERVAL),
+                Err(err) => return Err(format!("Error waiting for hook {}: {}", name, err)),
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::Priority;
+
+    fn temp_dir(test: &str) -> std::path::PathBuf {
+        let dir = env::temp_dir().join(format!("todocli-hooks-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn flags_name_the_events() {
+        assert!(matches!(
+            Hooks::flag_event("--on-add"),
+            Some(TaskEvent::Added)
+        ));
+        assert!(matches!(
+            Hooks::flag_event("--on-complete"),
+            Some(TaskEvent::Completed)
+        ));
+        assert!(matches!(
+            Hooks::flag_event("--on-remove"),
+            Some(TaskEvent::Removed)
+        ));
+        assert!(Hooks::flag_event("--on_add").is_none());
+        assert_eq!(env_var(TaskEvent::Completed), "TODO_HOOK_ON_COMPLETE");
+    }
+
+    #[test]
+    fn hook_gets_the_task_on_stdin_and_in_its_environment() {
+        let dir = temp_dir("stdin");
+        let mut hooks = Hooks::default();
+        hooks.set(
+            TaskEvent::Added,
+            format!(
+                "cat > {0}/task.json; echo \"$TODO_EVENT|$TODO_NAME|$TODO_PRIORITY\" > {0}/env",
+                dir.display()
+            ),
+        );
+        let task = Task::new("milk".to_owned(), "2 l".to_owned(), Priority::High);
+
+        hooks.run(TaskEvent::Added, &task).unwrap();
+
+        let sent: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(dir.join("task.json")).unwrap()).unwrap();
+        assert_eq!(sent, serde_json::to_value(&task).unwrap());
+        assert_eq!(
+            fs::read_to_string(dir.join("env")).unwrap(),
+            "on_add|milk|High\n"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn only_the_matching_hook_runs() {
+        let dir = temp_dir("matching");
+        let mut hooks = Hooks::default();
+        hooks.set(
+            TaskEvent::Removed,
+            format!("touch {}/removed", dir.display()),
+        );
+        let task = Task::new("milk".to_owned(), String::new(), Priority::Low);
+
+        hooks.run(TaskEvent::Added, &task).unwrap();
+        assert!(!dir.join("removed").exists());
+        hooks.run(TaskEvent::Removed, &task).unwrap();
+        assert!(dir.join("removed").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn failures_and_timeouts_are_reported() {
+        let task = Task::new("milk".to_owned(), String::new(), Priority::Low);
+        let mut hooks = Hooks::default();
+        hooks.set(TaskEvent::Completed, "exit 3".to_owned());
+        let msg = hooks.run(TaskEvent::Completed, &task).unwrap_err();
+        assert!(msg.starts_with("Hook on_complete failed"), "{}", msg);
+
+        hooks.timeout = Duration::from_millis(100);
+        hooks.set(TaskEvent::Completed, "sleep 10".to_owned());
+        let started = Instant::now();
+        let msg = hooks.run(TaskEvent::Completed, &task).unwrap_err();
+        assert!(msg.contains("timed out"), "{}", msg);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
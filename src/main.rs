@@ -1,295 +1,3482 @@
+mod backup;
+mod cli;
+mod diff;
+mod email;
+mod export;
+mod history;
+mod hooks;
+mod picker;
+mod pomodoro;
+mod report;
+mod rpc;
+mod server;
+mod table;
+mod webhook;
+
 use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    env,
+    fmt::Display,
     fs::File,
-    io::{BufReader, Write},
-    path::Path,
-    usize,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    process,
+    str::FromStr,
 };
 
-use chrono::{DateTime, Local};
+use backup::BackupPolicy;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use diff::TaskDiff;
+use export::ExportFormat;
+use history::SearchHistory;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize)]
+/// Declared lowest first, so the derived ordering ranks High above Medium above Low.
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone)]
 enum Priority {
     Low,
     Medium,
     High,
 }
 
-impl Priority {
-    fn to_string(&self) -> String {
+impl Display for Priority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Priority::Low => write!(f, "Low"),
+            Priority::Medium => write!(f, "Medium"),
+            Priority::High => write!(f, "High"),
+        }
+    }
+}
+
+impl FromStr for Priority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "low" => Ok(Priority::Low),
+            "medium" => Ok(Priority::Medium),
+            "high" => Ok(Priority::High),
+            _ => Err(format!("Unknown priority \"{}\"", s)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Default)]
+enum Status {
+    #[default]
+    Pending,
+    Done,
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Priority::Low => "Low".to_owned(),
-            Priority::Medium => "Medium".to_owned(),
-            Priority::High => "High".to_owned(),
+            Status::Pending => write!(f, "Pending"),
+            Status::Done => write!(f, "Done"),
+        }
+    }
+}
+
+impl FromStr for Status {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "pending" => Ok(Status::Pending),
+            "done" => Ok(Status::Done),
+            _ => Err(format!("Unknown status \"{}\"", s)),
         }
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 struct Task {
+    /// Assigned by `TasksManager`; 0 until the task is added.
+    #[serde(default)]
+    id: u64,
     name: String,
     description: String,
     priority: Priority,
     add_time: DateTime<Local>,
+    #[serde(default)]
+    pomodoros: u32,
+    #[serde(default)]
+    modified_time: Option<DateTime<Local>>,
+    #[serde(default)]
+    status: Status,
+    #[serde(default)]
+    completed_at: Option<DateTime<Local>>,
+    #[serde(default)]
+    due_date: Option<DateTime<Local>>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// Lowercases and trims tags, dropping empty and repeated ones.
+fn normalize_tags<'a>(tags: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut normalized: Vec<String> = vec![];
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+fn parse_tags(input: &str) -> Vec<String> {
+    normalize_tags(input.split(','))
+}
+
+/// Parses a deadline as `YYYY-MM-DD HH:MM` or `YYYY-MM-DD`; blank means no deadline.
+///
+/// A bare date is due at the end of that day.
+fn parse_due_date(input: &str) -> Result<Option<DateTime<Local>>, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok(None);
+    }
+
+    let naive = match NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M") {
+        Ok(naive) => naive,
+        Err(_) => match NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+            Ok(date) => date.and_time(NaiveTime::from_hms_opt(23, 59, 59).unwrap()),
+            Err(_) => {
+                return Err(format!(
+                    "Invalid due date \"{}\", expected YYYY-MM-DD or YYYY-MM-DD HH:MM",
+                    input
+                ));
+            }
+        },
+    };
+
+    match Local.from_local_datetime(&naive).earliest() {
+        Some(due_date) => Ok(Some(due_date)),
+        None => Err(format!(
+            "Due date \"{}\" doesn't exist in local time",
+            input
+        )),
+    }
+}
+
+/// Gives tasks without an id, or with one already taken, a fresh id.
+///
+/// Returns the next free id.
+fn number_tasks(tasks: &mut [Task]) -> u64 {
+    let mut next_id = tasks.iter().map(|task| task.id).max().unwrap_or(0) + 1;
+    let mut seen = HashSet::with_capacity(tasks.len());
+    for task in tasks {
+        if task.id == 0 || !seen.insert(task.id) {
+            task.id = next_id;
+            next_id += 1;
+            seen.insert(task.id);
+        }
+    }
+    next_id
 }
 
 impl Task {
     fn new(name: String, description: String, priority: Priority) -> Self {
         Self {
+            id: 0,
             name,
             description,
             priority,
             add_time: Local::now(),
+            pomodoros: 0,
+            modified_time: None,
+            status: Status::Pending,
+            completed_at: None,
+            due_date: None,
+            tags: vec![],
         }
     }
 
-    fn new_from_console() -> Self {
-        let name = ConsoleManager::input("Enter new task name: ").unwrap();
-        let description = ConsoleManager::input("Enter new task description: ").unwrap();
-        let priority = match ConsoleManager::input("Enter new task priority: ")
-            .unwrap()
-            .to_lowercase()
-            .as_str()
-        {
-            "low" => Priority::Low,
-            "medium" => Priority::Medium,
-            "high" => Priority::High,
-            _ => {
+    fn new_from_console() -> std::io::Result<Self> {
+        let name = ConsoleManager::input("Enter new task name: ")?;
+        let description = ConsoleManager::input("Enter new task description: ")?;
+        let priority = match ConsoleManager::input("Enter new task priority: ")?.parse() {
+            Ok(priority) => priority,
+            Err(_) => {
                 println!("Not valid input, setting to low");
                 Priority::Low
             }
         };
 
-        Self::new(name, description, priority)
+        let due_date = loop {
+            let input =
+                ConsoleManager::input("Enter due date (YYYY-MM-DD [HH:MM], blank for none): ")?;
+            match parse_due_date(&input) {
+                Ok(due_date) => break due_date,
+                Err(msg) => println!("{}", msg),
+            }
+        };
+
+        let tags = ConsoleManager::input("Enter tags (comma-separated, blank for none): ")?;
+
+        let mut task = Self::new(name, description, priority);
+        task.due_date = due_date;
+        task.tags = parse_tags(&tags);
+        Ok(task)
+    }
+
+    fn has_tag(&self, tag: &str) -> bool {
+        let tag = tag.trim().to_lowercase();
+        self.tags.contains(&tag)
+    }
+
+    /// Pending and past its deadline.
+    fn is_overdue(&self, now: DateTime<Local>) -> bool {
+        self.status == Status::Pending && self.due_date.is_some_and(|due_date| due_date < now)
+    }
+
+    /// When the task last changed; creation counts for never-edited tasks.
+    fn last_modified(&self) -> DateTime<Local> {
+        self.modified_time.unwrap_or(self.add_time)
     }
 
     fn print_task(&self) {
-        println!(
-            "{} | {} | {}\n\"{}\"\n",
+        let _ = self.write_task(&mut std::io::stdout().lock(), &table::Styling::for_stdout());
+    }
+
+    fn write_task(&self, out: &mut impl Write, styling: &table::Styling) -> std::io::Result<()> {
+        let status = table::status_label(self);
+        let due = match table::due_label(self, Local::now()) {
+            (label, _) if label.is_empty() => String::new(),
+            (label, color) => format!(" | Due {}", styling.paint(&label, color)),
+        };
+        writeln!(
+            out,
+            "#{} {} | {} | {} | {}{}\n\"{}\"\n",
+            self.id,
             self.name,
-            self.priority.to_string(),
-            self.add_time.format("%d-%m-%Y %H:%M:%S"),
+            styling.paint(
+                &self.priority.to_string(),
+                table::priority_color(&self.priority)
+            ),
+            table::format_time(self.add_time),
+            status,
+            due,
             self.description
-        )
+        )?;
+        if !self.tags.is_empty() {
+            writeln!(out, "Tags: {}\n", self.tags.join(", "))?;
+        }
+        if self.pomodoros > 0 {
+            writeln!(out, "Pomodoros: {}\n", self.pomodoros)?;
+        }
+        Ok(())
+    }
+}
+
+/// Task fields as sent by the HTTP and RPC front ends.
+#[derive(Deserialize)]
+struct TaskPayload {
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    priority: Option<String>,
+    #[serde(default)]
+    due_date: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+impl TaskPayload {
+    fn into_task(self) -> Result<Task, String> {
+        if self.name.trim().is_empty() {
+            return Err("Task name must not be empty".to_owned());
+        }
+
+        let priority = match self.priority {
+            Some(priority) => priority.parse()?,
+            None => Priority::Low,
+        };
+
+        let due_date = match self.due_date {
+            Some(due_date) => parse_due_date(&due_date)?,
+            None => None,
+        };
+
+        let mut task = Task::new(self.name, self.description, priority);
+        task.due_date = due_date;
+        task.tags = normalize_tags(self.tags.iter().map(String::as_str));
+        Ok(task)
+    }
+}
+
+/// Task fields to change as sent by the RPC front end; omitted fields are kept.
+#[derive(Deserialize)]
+struct TaskPatchPayload {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    priority: Option<String>,
+    /// A blank date clears the due date.
+    #[serde(default)]
+    due_date: Option<String>,
+    #[serde(default)]
+    tags: Option<Vec<String>>,
+}
+
+impl TaskPatchPayload {
+    fn into_patch(self) -> Result<TaskPatch, String> {
+        if self
+            .name
+            .as_ref()
+            .is_some_and(|name| name.trim().is_empty())
+        {
+            return Err("Task name must not be empty".to_owned());
+        }
+
+        let priority = match self.priority {
+            Some(priority) => Some(priority.parse()?),
+            None => None,
+        };
+
+        let due_date = match self.due_date {
+            Some(due_date) => Some(parse_due_date(&due_date)?),
+            None => None,
+        };
+
+        Ok(TaskPatch {
+            name: self.name,
+            description: self.description,
+            priority,
+            due_date,
+            tags: self.tags,
+        })
+    }
+}
+
+/// Task fields to change in an edit; `None` keeps the current value.
+#[derive(Default)]
+struct TaskPatch {
+    name: Option<String>,
+    description: Option<String>,
+    priority: Option<Priority>,
+    /// `Some(None)` clears the due date.
+    due_date: Option<Option<DateTime<Local>>>,
+    tags: Option<Vec<String>>,
+}
+
+impl TaskPatch {
+    fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.description.is_none()
+            && self.priority.is_none()
+            && self.due_date.is_none()
+            && self.tags.is_none()
+    }
+}
+
+#[derive(Clone, Copy)]
+enum SortKey {
+    Name,
+    Priority,
+    AddTime,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum SaveMode {
+    Overwrite,
+    FailIfExists,
+    /// Combine with the file's tasks by name, the newer edit winning.
+    Merge,
+}
+
+const MAX_UNDO: usize = 20;
+
+/// A task lifecycle change announced to the webhook and hooks.
+#[derive(Clone, Copy)]
+enum TaskEvent {
+    Added,
+    Completed,
+    Removed,
+}
+
+impl Display for TaskEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TaskEvent::Added => write!(f, "added"),
+            TaskEvent::Completed => write!(f, "completed"),
+            TaskEvent::Removed => write!(f, "removed"),
+        }
     }
 }
 
+/// What an undo needs to reverse one change; tasks are found again by id.
+enum Change {
+    Added { id: u64 },
+    Removed { index: usize, task: Task },
+    Edited { before: Task },
+    Reordered { ids: Vec<u64> },
+}
+
 struct TasksManager {
     tasks: Vec<Task>,
+    backup_policy: BackupPolicy,
+    webhook: Option<webhook::Webhook>,
+    hooks: hooks::Hooks,
+    /// File the list was last loaded from or saved to.
+    active_file: Option<String>,
+    /// Mutations since the list was last saved or replaced by a load.
+    unsaved_changes: usize,
+    last_saved: Option<DateTime<Local>>,
+    /// The tasks as of the last load or save, for reviewing changes.
+    saved: Vec<Task>,
+    /// Position of the first task with each name, rebuilt lazily after changes.
+    name_index: RefCell<Option<HashMap<String, usize>>>,
+    next_id: u64,
+    /// Most recent change last.
+    undo_history: VecDeque<Change>,
+    /// Set when a load dropped the history, so "nothing to undo" can say why.
+    undo_cleared: bool,
 }
 
 impl TasksManager {
     fn new() -> Self {
-        Self { tasks: vec![] }
+        Self {
+            tasks: vec![],
+            backup_policy: BackupPolicy::default(),
+            webhook: None,
+            hooks: hooks::Hooks::default(),
+            active_file: None,
+            unsaved_changes: 0,
+            last_saved: None,
+            saved: vec![],
+            name_index: RefCell::new(None),
+            next_id: 1,
+            undo_history: VecDeque::new(),
+            undo_cleared: false,
+        }
     }
 
-    fn print_tasks(&self) {
-        for task in &self.tasks {
-            task.print_task();
-        }
+    fn active_file(&self) -> Option<&str> {
+        self.active_file.as_deref()
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.unsaved_changes > 0
+    }
+
+    fn unsaved_changes(&self) -> usize {
+        self.unsaved_changes
+    }
+
+    fn last_saved(&self) -> Option<DateTime<Local>> {
+        self.last_saved
+    }
+
+    /// Binds the list to `filename` as of its last write on disk.
+    fn mark_synced(&mut self, filename: &str, written: Option<DateTime<Local>>) {
+        self.active_file = Some(filename.to_owned());
+        self.unsaved_changes = 0;
+        self.last_saved = written;
+        self.saved = self.tasks.clone();
+    }
+
+    fn changes_since_save(&self) -> TaskDiff<'_> {
+        diff::diff_tasks(&self.saved, &self.tasks)
+    }
+
+    /// Prints all tasks, or only those with the given status.
+    fn print_tasks(&self, status: Option<Status>) {
+        let tasks: Vec<&Task> = self
+            .tasks
+            .iter()
+            .filter(|task| status.is_none_or(|status| task.status == status))
+            .collect();
+        table::print(&tasks);
+    }
+
+    fn add_task(&mut self, task: Task) -> Result<String, String> {
+        let msg = self.insert_task(task)?;
+        let warning = self.notify(TaskEvent::Added, self.tasks.len() - 1);
+        Ok(with_warning(&msg, warning))
     }
 
-    fn add_task(&mut self, task: Task) {
+    /// Adds a task without announcing it, for bulk imports.
+    fn insert_task(&mut self, mut task: Task) -> Result<String, String> {
+        if self.find_task(&task.name).is_some() {
+            return Err(format!("Task with name \"{}\" already exists", task.name));
+        }
+
+        task.id = self.next_id;
+        self.next_id += 1;
+        if let Some(index) = self.name_index.get_mut() {
+            index.insert(task.name.clone(), self.tasks.len());
+        }
+        let msg = format!("Task \"{}\" added with id {}", task.name, task.id);
+        self.record_change(Change::Added { id: task.id });
         self.tasks.push(task);
+        self.unsaved_changes += 1;
+        Ok(msg)
+    }
+
+    fn find_task(&self, name: &str) -> Option<usize> {
+        let mut index = self.name_index.borrow_mut();
+        let index = index.get_or_insert_with(|| {
+            let mut index = HashMap::with_capacity(self.tasks.len());
+            for (position, task) in self.tasks.iter().enumerate() {
+                index.entry(task.name.clone()).or_insert(position);
+            }
+            index
+        });
+        index.get(name).copied()
+    }
+
+    /// Indices of tasks whose name or description contains `query`, ignoring case.
+    ///
+    /// A blank query matches nothing rather than everything.
+    fn search_tasks(&self, query: &str) -> Vec<usize> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return vec![];
+        }
+        self.tasks
+            .iter()
+            .enumerate()
+            .filter(|(_, task)| {
+                task.name.to_lowercase().contains(&query)
+                    || task.description.to_lowercase().contains(&query)
+            })
+            .map(|(index, _)| index)
+            .collect()
     }
 
-    fn find_task(&self, name: String) -> Option<usize> {
-        self.tasks.iter().position(|task| task.name == name)
+    /// Finds a task by `#id`, exact name, or bare id, in that order.
+    fn lookup(&self, key: &str) -> Option<usize> {
+        let by_id = |id: &str| {
+            let id: u64 = id.parse().ok()?;
+            self.tasks.iter().position(|task| task.id == id)
+        };
+
+        if let Some(index) = key.strip_prefix('#').and_then(by_id) {
+            return Some(index);
+        }
+        self.find_task(key).or_else(|| by_id(key))
+    }
+
+    /// Gives tasks loaded without an id, or with one already taken, the next free id.
+    fn assign_ids(&mut self) {
+        self.next_id = number_tasks(&mut self.tasks);
+    }
+
+    /// Drops the name index after a change that can move or rename tasks.
+    fn invalidate_index(&mut self) {
+        *self.name_index.get_mut() = None;
     }
 
     fn remove_task(&mut self, name: &str) -> Result<String, String> {
-        if let Some(index) = self.find_task(name.to_string()) {
-            self.tasks.remove(index);
-            Ok(format!("Task \"{}\" removed successfully", name))
+        if let Some(index) = self.lookup(name) {
+            let warning = self.notify(TaskEvent::Removed, index);
+            let task = self.tasks.remove(index);
+            let msg = format!("Task \"{}\" removed successfully", task.name);
+            self.record_change(Change::Removed { index, task });
+            self.invalidate_index();
+            self.unsaved_changes += 1;
+            Ok(with_warning(&msg, warning))
         } else {
             Err(format!("Task with name \"{}\" doesn't exist", name))
         }
     }
 
-    fn edit_task(&mut self, name: &str, updated_task: Task) -> Result<String, String> {
-        if let Some(index) = self.find_task(name.to_string()) {
-            match self.tasks.get_mut(index) {
-                Some(task) => {
-                    task.name = updated_task.name;
-                    task.description = updated_task.description;
-                    task.priority = updated_task.priority;
-                    Ok(format!("Task \"{}\" updated successfully", name))
+    /// Overwrites only the fields set in `patch`; `add_time` is never touched.
+    fn update_task(&mut self, name: &str, patch: TaskPatch) -> Result<String, String> {
+        let Some(index) = self.lookup(name) else {
+            return Err(format!("Task with name \"{}\" doesn't exist", name));
+        };
+        if let Some(new_name) = &patch.name {
+            if self.find_task(new_name).is_some_and(|other| other != index) {
+                return Err(format!("Task with name \"{}\" already exists", new_name));
+            }
+        }
+
+        self.record_change(Change::Edited {
+            before: self.tasks[index].clone(),
+        });
+        let task = &mut self.tasks[index];
+        let msg = format!("Task \"{}\" updated successfully", task.name);
+        if let Some(name) = patch.name {
+            task.name = name;
+        }
+        if let Some(description) = patch.description {
+            task.description = description;
+        }
+        if let Some(priority) = patch.priority {
+            task.priority = priority;
+        }
+        if let Some(due_date) = patch.due_date {
+            task.due_date = due_date;
+        }
+        if let Some(tags) = patch.tags {
+            task.tags = normalize_tags(tags.iter().map(String::as_str));
+        }
+        task.modified_time = Some(Local::now());
+        self.unsaved_changes += 1;
+        self.invalidate_index();
+        Ok(msg)
+    }
+
+    fn mark_done(&mut self, name: &str) -> Result<String, String> {
+        match self.lookup(name) {
+            Some(index) => {
+                if self.tasks[index].status == Status::Done {
+                    return Err(format!(
+                        "Task \"{}\" is already done",
+                        self.tasks[index].name
+                    ));
                 }
-                None => Err("Error borrowing task".to_owned()),
+
+                self.record_change(Change::Edited {
+                    before: self.tasks[index].clone(),
+                });
+                let task = &mut self.tasks[index];
+                let now = Local::now();
+                task.status = Status::Done;
+                task.completed_at = Some(now);
+                task.modified_time = Some(now);
+                let msg = format!("Task \"{}\" marked as done", task.name);
+                self.unsaved_changes += 1;
+                Ok(with_warning(&msg, self.notify(TaskEvent::Completed, index)))
             }
-        } else {
-            Err(format!("Task with name \"{}\" doesn't exist", name))
+            None => Err(format!("Task with name \"{}\" doesn't exist", name)),
         }
     }
 
-    fn store_to_file(&self, filename: &str) -> Result<String, String> {
-        if !Path::new(filename).exists() {
-            let file = match File::create(filename) {
-                Ok(file) => file,
-                Err(_) => return Err("File already exist".to_owned()),
-            };
+    /// Tells the webhook and runs the hook, if any, for a change to the task at `index`.
+    ///
+    /// The change stands either way; failures come back as a warning.
+    fn notify(&self, event: TaskEvent, index: usize) -> Option<String> {
+        let task = &self.tasks[index];
+        let failures: Vec<String> = [
+            self.webhook
+                .as_ref()
+                .and_then(|webhook| webhook.notify(event, task).err()),
+            self.hooks.run(event, task).err(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        (!failures.is_empty()).then(|| failures.join("; "))
+    }
 
-            match serde_json::to_writer(&file, &self.tasks) {
-                Ok(_) => Ok("Data stored successfully".to_owned()),
-                Err(err) => Err(format!("Error saving data {}", err)),
+    /// Raises a task's priority one level.
+    fn promote_task(&mut self, name: &str) -> Result<String, String> {
+        match self.lookup(name) {
+            Some(index) => {
+                let before = self.tasks[index].clone();
+                let task = &mut self.tasks[index];
+                task.priority = match task.priority {
+                    Priority::Low => Priority::Medium,
+                    Priority::Medium => Priority::High,
+                    Priority::High => {
+                        return Err(format!("Task \"{}\" is already High priority", task.name))
+                    }
+                };
+                task.modified_time = Some(Local::now());
+                let msg = format!(
+                    "Task \"{}\" promoted to {} priority",
+                    task.name, task.priority
+                );
+                self.record_change(Change::Edited { before });
+                self.unsaved_changes += 1;
+                Ok(msg)
             }
-        } else {
-            Err("File \"{filename}\" already exists".to_owned())
+            None => Err(format!("Task with name \"{}\" doesn't exist", name)),
         }
     }
 
-    fn read_from_file(&mut self, filename: &str) -> Result<String, String> {
-        if Path::new(filename).exists() {
-            let file = match File::open(filename) {
-                Ok(file) => file,
-                Err(_) => return Err("File doesn't exist".to_owned()),
-            };
+    /// Indices of the `limit` most recently modified tasks, most recent first.
+    fn recently_modified(&self, limit: usize) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..self.tasks.len()).collect();
+        indices.sort_by_key(|&index| std::cmp::Reverse(self.tasks[index].last_modified()));
+        indices.truncate(limit);
+        indices
+    }
 
-            let reader = BufReader::new(file);
+    /// Indices of the `limit` oldest pending tasks, oldest first.
+    fn oldest_tasks(&self, limit: usize) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..self.tasks.len())
+            .filter(|&index| self.tasks[index].status == Status::Pending)
+            .collect();
+        indices.sort_by_key(|&index| self.tasks[index].add_time);
+        indices.truncate(limit);
+        indices
+    }
 
-            self.tasks = match serde_json::from_reader(reader) {
-                Ok(data) => data,
-                Err(err) => return Err(format!("Error reading file {}", err)),
+    /// Reorders the tasks by `by`; ties fall back to the oldest first, whatever the direction.
+    fn sort_tasks(&mut self, by: SortKey, descending: bool) {
+        self.record_order();
+        self.tasks.sort_by(|a, b| {
+            let order = match by {
+                SortKey::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+                SortKey::Priority => a.priority.cmp(&b.priority),
+                SortKey::AddTime => a.add_time.cmp(&b.add_time),
             };
+            let order = if descending { order.reverse() } else { order };
+            order.then(a.add_time.cmp(&b.add_time))
+        });
+        self.unsaved_changes += 1;
+        self.invalidate_index();
+    }
 
-            Ok("Data read successfully".to_owned())
-        } else {
-            Err("File \"{filename}\" doesn't exist".to_owned())
+    fn record_change(&mut self, change: Change) {
+        if self.undo_history.len() == MAX_UNDO {
+            self.undo_history.pop_front();
         }
+        self.undo_history.push_back(change);
+        self.undo_cleared = false;
     }
-}
 
-struct ConsoleManager {
-    tasks_manager: TasksManager,
-    menu_options: Vec<String>,
-}
+    fn record_order(&mut self) {
+        let ids = self.tasks.iter().map(|task| task.id).collect();
+        self.record_change(Change::Reordered { ids });
+    }
 
-impl ConsoleManager {
-    fn new() -> Self {
-        Self {
-            tasks_manager: TasksManager::new(),
-            menu_options: vec![
-                "Add task".to_owned(),
-                "Find task".to_owned(),
-                "Edit task".to_owned(),
-                "Remove task".to_owned(),
-                "Print tasks".to_owned(),
-                "Store tasks to file".to_owned(),
-                "Read tasks from file".to_owned(),
-            ],
+    /// Forgets the history once the list is replaced wholesale, as by a load.
+    fn clear_undo(&mut self) {
+        if !self.undo_history.is_empty() {
+            self.undo_history.clear();
+            self.undo_cleared = true;
         }
     }
 
-    fn print_menu(&self) {
-        for (index, menu_option) in self.menu_options.iter().enumerate() {
-            println!("{}. {}", index + 1, menu_option);
-        }
+    /// Reverses the most recent change still in the history.
+    ///
+    /// Loading a file, restoring a backup or merging on save replace the whole list and
+    /// clear the history, so changes before them can't be undone.
+    fn undo(&mut self) -> Result<String, String> {
+        let Some(change) = self.undo_history.pop_back() else {
+            return Err(if self.undo_cleared {
+                "Nothing to undo, the history was cleared when the task list was replaced from a file"
+                    .to_owned()
+            } else {
+                "Nothing to undo".to_owned()
+            });
+        };
+        let position = |tasks: &[Task], id: u64| {
+            tasks
+                .iter()
+                .position(|task| task.id == id)
+                .ok_or_else(|| format!("Task with id {} no longer exists", id))
+        };
+
+        let msg = match change {
+            Change::Added { id } => {
+                let task = self.tasks.remove(position(&self.tasks, id)?);
+                format!("Undid adding \"{}\"", task.name)
+            }
+            Change::Removed { index, task } => {
+                let msg = format!("Restored removed task \"{}\"", task.name);
+                self.tasks.insert(index.min(self.tasks.len()), task);
+                msg
+            }
+            Change::Edited { before } => {
+                let index = position(&self.tasks, before.id)?;
+                let msg = format!("Undid changes to \"{}\"", before.name);
+                self.tasks[index] = before;
+                msg
+            }
+            Change::Reordered { ids } => {
+                let order: HashMap<u64, usize> = ids
+                    .into_iter()
+                    .enumerate()
+                    .map(|(position, id)| (id, position))
+                    .collect();
+                self.tasks
+                    .sort_by_key(|task| order.get(&task.id).copied().unwrap_or(usize::MAX));
+                "Restored the previous task order".to_owned()
+            }
+        };
+        self.invalidate_index();
+        self.unsaved_changes += 1;
+        Ok(msg)
     }
 
-    fn input(query: &str) -> std::io::Result<String> {
-        print!("{}", query);
-        std::io::stdout().flush()?;
+    fn filter_by_tag(&self, tag: &str) -> Vec<&Task> {
+        self.tasks.iter().filter(|task| task.has_tag(tag)).collect()
+    }
 
-        let mut buffer = String::new();
-        std::io::stdin().read_line(&mut buffer)?;
-        Ok(buffer.trim().to_owned())
+    /// Every tag in use with the number of tasks carrying it, by tag name.
+    fn list_tags(&self) -> Vec<(String, usize)> {
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for tag in self.tasks.iter().flat_map(|task| &task.tags) {
+            *counts.entry(tag).or_insert(0) += 1;
+        }
+        counts
+            .into_iter()
+            .map(|(tag, count)| (tag.to_owned(), count))
+            .collect()
     }
 
-    fn process_command(&mut self) {
-        match Self::input("\nEnter command index: ") {
-            Ok(command) => match command.as_str() {
-                "1" => {
-                    self.tasks_manager.add_task(Task::new_from_console());
-                }
-                "2" => {
-                    let name = match Self::input("Enter task name to find: ") {
-                        Ok(name) => name,
-                        Err(err) => {
-                            println!("Error geting user input {}", err);
-                            return;
-                        }
-                    };
+    fn filter_by_priority(&self, priority: Priority) -> Vec<&Task> {
+        self.tasks
+            .iter()
+            .filter(|task| task.priority == priority)
+            .collect()
+    }
 
-                    match self.tasks_manager.find_task(name.clone()) {
-                        Some(index) => self.tasks_manager.tasks.get(index).unwrap().print_task(),
-                        None => println!("Task with name \"{}\" doesn't exist", name),
-                    }
-                }
-                "3" => {
-                    let name = match Self::input("Enter task name to edit: ") {
-                        Ok(name) => name,
-                        Err(err) => {
-                            println!("Error geting user input {}", err);
-                            return;
-                        }
-                    };
+    /// Orders tasks by deadline, soonest first, with undated tasks last in their current order.
+    fn sort_by_due_date(&mut self) {
+        self.record_order();
+        self.tasks
+            .sort_by_key(|task| (task.due_date.is_none(), task.due_date));
+        self.unsaved_changes += 1;
+        self.invalidate_index();
+    }
 
-                    match self
-                        .tasks_manager
-                        .edit_task(&name, Task::new_from_console())
-                    {
-                        Ok(msg) => println!("{}", msg),
-                        Err(msg) => println!("{}", msg),
-                    }
-                }
-                "4" => {
-                    let name = match Self::input("Enter task name to remove: ") {
-                        Ok(name) => name,
-                        Err(err) => {
-                            println!("Error geting user input {}", err);
-                            return;
-                        }
-                    };
+    fn export(&self, filename: &str, format: ExportFormat) -> Result<String, String> {
+        let text = match format {
+            ExportFormat::Csv => export::to_csv(&self.tasks),
+            ExportFormat::Markdown => export::to_markdown(&self.tasks),
+        };
+        match std::fs::write(filename, text) {
+            Ok(_) => Ok(format!(
+                "Exported {} tasks to \"{}\"",
+                self.tasks.len(),
+                filename
+            )),
+            Err(err) => Err(format!("Error writing file \"{}\": {}", filename, err)),
+        }
+    }
 
-                    match self.tasks_manager.remove_task(&name) {
-                        Ok(msg) => println!("{}", msg),
-                        Err(msg) => println!("{}", msg),
-                    }
-                }
-                "5" => {
-                    self.tasks_manager.print_tasks();
-                }
-                "6" => {
-                    let filename = match Self::input("Enter file name to save: ") {
-                        Ok(name) => name,
-                        Err(err) => {
-                            println!("Error geting user input {}", err);
-                            return;
-                        }
-                    };
+    /// Adds the tasks from a CSV file, skipping names that are already taken.
+    fn import_csv(&mut self, filename: &str) -> Result<String, String> {
+        let text = std::fs::read_to_string(filename)
+            .map_err(|err| format!("Error reading file \"{}\": {}", filename, err))?;
+        let tasks = export::tasks_from_csv(&text, Local::now())
+            .map_err(|msg| format!("Error importing \"{}\": {}", filename, msg))?;
 
-                    match self.tasks_manager.store_to_file(filename.as_str()) {
-                        Ok(msg) => println!("{}", msg),
-                        Err(msg) => println!("{}", msg),
-                    }
-                }
-                "7" => {
-                    let filename = match Self::input("Enter file name to open: ") {
-                        Ok(name) => name,
-                        Err(err) => {
-                            println!("Error geting user input {}", err);
-                            return;
-                        }
-                    };
+        let (mut added, mut skipped) = (0, 0);
+        for task in tasks {
+            match self.insert_task(task) {
+                Ok(_) => added += 1,
+                Err(_) => skipped += 1,
+            }
+        }
+        Ok(format!(
+            "Imported {} tasks, skipped {} duplicates",
+            added, skipped
+        ))
+    }
 
-                    match self.tasks_manager.read_from_file(filename.as_str()) {
-                        Ok(msg) => println!("{}", msg),
-                        Err(msg) => println!("{}", msg),
-                    }
-                }
-                _ => println!("I don't understand this command"),
-            },
-            Err(err) => println!("Error geting user input {err}"),
+    fn log_pomodoro(&mut self, name: &str) -> Result<String, String> {
+        match self.lookup(name) {
+            Some(index) => {
+                self.record_change(Change::Edited {
+                    before: self.tasks[index].clone(),
+                });
+                let task = &mut self.tasks[index];
+                task.pomodoros += 1;
+                task.modified_time = Some(Local::now());
+                self.unsaved_changes += 1;
+                Ok(format!(
+                    "Logged pomodoro #{} on task \"{}\"",
+                    task.pomodoros, task.name
+                ))
+            }
+            None => Err(format!("Task with name \"{}\" doesn't exist", name)),
         }
     }
-}
 
-fn main() {
-    let mut manager = ConsoleManager::new();
-    manager.print_menu();
+    /// Saves the tasks to `filename`, handling an existing file according to `mode`.
+    ///
+    /// An existing file is backed up before it is replaced.
+    fn store_to_file(&mut self, filename: &str, mode: SaveMode) -> Result<String, String> {
+        self.save(filename, mode, true)
+    }
 
-    loop {
-        manager.process_command();
-        println!();
+    /// Overwrites `filename` without backing it up first, for repeated saves of one session.
+    fn store_without_backup(&mut self, filename: &str) -> Result<String, String> {
+        self.save(filename, SaveMode::Overwrite, false)
+    }
+
+    fn save(&mut self, filename: &str, mode: SaveMode, backup: bool) -> Result<String, String> {
+        let exists = Path::new(filename).exists();
+        if exists && mode == SaveMode::FailIfExists {
+            return Err(format!("File \"{}\" already exists", filename));
+        }
+
+        let warning = if exists && backup {
+            self.check_backup(backup::backup_file(filename, &self.backup_policy))?
+        } else {
+            None
+        };
+
+        // A merge only replaces the in-memory tasks once the merged list is written.
+        let (merged, msg) = if exists && mode == SaveMode::Merge {
+            let (merged, added, updated) = self.merged_with(Self::read_tasks(Path::new(filename))?);
+            let msg = format!(
+                "Data merged successfully ({} added, {} updated)",
+                added, updated
+            );
+            (Some(merged), msg)
+        } else {
+            (None, "Data stored successfully".to_owned())
+        };
+
+        let file = match File::create(filename) {
+            Ok(file) => file,
+            Err(err) => return Err(format!("Error creating file \"{}\": {}", filename, err)),
+        };
+
+        match Self::write_tasks(file, merged.as_deref().unwrap_or(&self.tasks)) {
+            Ok(_) => {
+                if let Some(merged) = merged {
+                    self.tasks = merged;
+                    self.clear_undo();
+                    self.assign_ids();
+                    self.invalidate_index();
+                }
+                self.mark_synced(filename, Some(Local::now()));
+                Ok(with_warning(&msg, warning))
+            }
+            Err(err) => Err(format!("Error saving data {}", err)),
+        }
+    }
+
+    /// Folds the in-memory tasks into `existing` by name, keeping the newer edit.
+    ///
+    /// Returns the merged list, numbered, and how many tasks were added to and
+    /// updated in `existing`.
+    fn merged_with(&self, existing: Vec<Task>) -> (Vec<Task>, usize, usize) {
+        let mut merged = existing;
+        let mut positions: HashMap<String, usize> = HashMap::with_capacity(merged.len());
+        for (position, task) in merged.iter().enumerate() {
+            positions.entry(task.name.clone()).or_insert(position);
+        }
+
+        let (mut added, mut updated) = (0, 0);
+        for task in self.tasks.iter().cloned() {
+            match positions.get(&task.name) {
+                Some(&position) => {
+                    if task.last_modified() > merged[position].last_modified() {
+                        merged[position] = task;
+                        updated += 1;
+                    }
+                }
+                None => {
+                    positions.insert(task.name.clone(), merged.len());
+                    merged.push(task);
+                    added += 1;
+                }
+            }
+        }
+
+        number_tasks(&mut merged);
+        (merged, added, updated)
+    }
+
+    /// Turns a failed backup into an error under a strict policy, or a warning otherwise.
+    fn check_backup<T>(&self, result: Result<T, String>) -> Result<Option<String>, String> {
+        match result {
+            Ok(_) => Ok(None),
+            Err(msg) if self.backup_policy.strict => Err(msg),
+            Err(msg) => Ok(Some(msg)),
+        }
+    }
+
+    /// Replaces the tasks with the contents of `filename`.
+    ///
+    /// Unsaved in-memory tasks are backed up first.
+    fn read_from_file(&mut self, filename: &str) -> Result<String, String> {
+        if Path::new(filename).exists() {
+            let tasks = Self::read_tasks(Path::new(filename))?;
+            let warning = self.backup_unsaved(filename)?;
+
+            let written = std::fs::metadata(filename)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .map(DateTime::<Local>::from);
+
+            self.tasks = tasks;
+            self.clear_undo();
+            self.assign_ids();
+            self.invalidate_index();
+            self.mark_synced(filename, written);
+            Ok(with_warning("Data read successfully", warning))
+        } else {
+            Err(format!("File \"{}\" doesn't exist", filename))
+        }
+    }
+
+    /// Makes a backup's tasks the working list, leaving `filename` on disk untouched.
+    fn restore_backup(&mut self, backup: &Path, filename: &str) -> Result<String, String> {
+        let tasks = Self::read_tasks(backup)?;
+        let warning = self.backup_unsaved(filename)?;
+
+        self.tasks = tasks;
+        self.clear_undo();
+        self.assign_ids();
+        self.invalidate_index();
+        self.unsaved_changes += 1;
+        Ok(with_warning(
+            &format!("Backup \"{}\" restored, save to keep it", backup.display()),
+            warning,
+        ))
+    }
+
+    /// Backs up unsaved tasks before they are replaced, filed under the file they
+    /// came from so "Restore backup" can find them; `filename` is the fallback.
+    fn backup_unsaved(&self, filename: &str) -> Result<Option<String>, String> {
+        if self.is_dirty() && !self.tasks.is_empty() {
+            self.check_backup(backup::backup_tasks(
+                self.active_file().unwrap_or(filename),
+                &self.tasks,
+                &self.backup_policy,
+            ))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn read_tasks(filename: &Path) -> Result<Vec<Task>, String> {
+        // Parsing from a slice is much faster than from a reader on big files.
+        let data = match std::fs::read(filename) {
+            Ok(data) => data,
+            Err(_) => return Err("File doesn't exist".to_owned()),
+        };
+
+        match serde_json::from_slice(&data) {
+            Ok(data) => Ok(data),
+            Err(err) => Err(format!("Error reading file {}", err)),
+        }
+    }
+
+    fn write_tasks(file: File, tasks: &[Task]) -> serde_json::Result<()> {
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer(&mut writer, tasks)?;
+        writer.flush().map_err(serde_json::Error::io)
+    }
+}
+
+/// Describes how long before `now` something happened, e.g. "5 minutes ago".
+fn time_ago(time: DateTime<Local>, now: DateTime<Local>) -> String {
+    let elapsed = now - time;
+    let (amount, unit) = if elapsed.num_days() > 0 {
+        (elapsed.num_days(), "day")
+    } else if elapsed.num_hours() > 0 {
+        (elapsed.num_hours(), "hour")
+    } else if elapsed.num_minutes() > 0 {
+        (elapsed.num_minutes(), "minute")
+    } else {
+        return "just now".to_owned();
+    };
+
+    if amount == 1 {
+        format!("1 {} ago", unit)
+    } else {
+        format!("{} {}s ago", amount, unit)
+    }
+}
+
+fn with_warning(msg: &str, warning: Option<String>) -> String {
+    match warning {
+        Some(warning) => format!("{} (warning: {})", msg, warning),
+        None => msg.to_owned(),
+    }
+}
+
+const DEFAULT_DATA_FILE: &str = "tasks.json";
+
+/// Environment variables naming the data file, in order of precedence.
+const DATA_FILE_VARS: [&str; 2] = ["TODO_CLI_FILE", "TODO_FILE"];
+
+enum PathSource {
+    Flag,
+    Env(&'static str),
+    Default,
+    Opened,
+}
+
+impl Display for PathSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathSource::Flag => write!(f, "--file flag"),
+            PathSource::Env(var) => write!(f, "{} environment variable", var),
+            PathSource::Default => write!(f, "built-in default"),
+            PathSource::Opened => write!(f, "Manage files"),
+        }
+    }
+}
+
+struct DataFile {
+    path: String,
+    source: PathSource,
+}
+
+impl DataFile {
+    /// Picks the data file path: `--file` flag, then the environment, then the built-in default.
+    fn resolve(flag: Option<String>) -> Self {
+        if let Some(path) = flag {
+            return Self {
+                path,
+                source: PathSource::Flag,
+            };
+        }
+
+        for var in DATA_FILE_VARS {
+            match env::var(var) {
+                Ok(path) if !path.is_empty() => {
+                    return Self {
+                        path,
+                        source: PathSource::Env(var),
+                    };
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            path: Self::default_path(),
+            source: PathSource::Default,
+        }
+    }
+
+    /// `$XDG_DATA_HOME/todocli/tasks.json`, falling back to `~/.local/share`, or the
+    /// working directory when there is no home directory.
+    fn default_path() -> String {
+        let data_home = match env::var("XDG_DATA_HOME") {
+            Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => match env::var("HOME") {
+                Ok(home) if !home.is_empty() => Path::new(&home).join(".local").join("share"),
+                _ => return DEFAULT_DATA_FILE.to_owned(),
+            },
+        };
+        data_home
+            .join("todocli")
+            .join(DEFAULT_DATA_FILE)
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Creates the directory the data file lives in, so saving to it can succeed.
+    fn create_dir(&self) -> Result<(), String> {
+        match Path::new(&self.path).parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => std::fs::create_dir_all(dir)
+                .map_err(|err| format!("Error creating directory \"{}\": {}", dir.display(), err)),
+            _ => Ok(()),
+        }
+    }
+}
+
+const DEFAULT_PORT: u16 = 7777;
+const DEFAULT_RECENT_LIMIT: usize = 5;
+/// Columns of description shown next to each name in the task picker.
+const PICKER_DESCRIPTION_WIDTH: usize = 40;
+
+enum Command {
+    Interactive,
+    Serve { port: u16 },
+    Rpc,
+    Run(cli::Action),
+}
+
+struct Args {
+    file: Option<String>,
+    command: Command,
+    backup_policy: BackupPolicy,
+    read_only: bool,
+    recent_limit: usize,
+    autosave: bool,
+    webhook: Option<String>,
+    hooks: hooks::Hooks,
+    no_hooks: bool,
+}
+
+impl Args {
+    fn parse(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut file = None;
+        let mut command = Command::Interactive;
+        let mut backup_policy = BackupPolicy::default();
+        let mut read_only = false;
+        let mut recent_limit = DEFAULT_RECENT_LIMIT;
+        let mut autosave = true;
+        let mut webhook = None;
+        let mut hooks = hooks::Hooks::default();
+        let mut no_hooks = false;
+        let mut args = args;
+
+        while let Some(arg) = args.next() {
+            if arg == "--file" {
+                match args.next() {
+                    Some(path) => file = Some(path),
+                    None => return Err("Missing value for --file".to_owned()),
+                }
+            } else if let Some(path) = arg.strip_prefix("--file=") {
+                file = Some(path.to_owned());
+            } else if arg == "--max-backups" {
+                backup_policy.max_backups = match args.next().map(|value| value.parse()) {
+                    Some(Ok(value)) => value,
+                    _ => return Err("Expected a number after --max-backups".to_owned()),
+                };
+            } else if arg == "--strict-backups" {
+                backup_policy.strict = true;
+            } else if arg == "--recent" {
+                recent_limit = match args.next().map(|value| value.parse()) {
+                    Some(Ok(value)) => value,
+                    _ => return Err("Expected a number after --recent".to_owned()),
+                };
+            } else if arg == "--read-only" {
+                read_only = true;
+            } else if arg == "--no-autosave" {
+                autosave = false;
+            } else if arg == "--webhook" {
+                match args.next() {
+                    Some(url) => webhook = Some(url),
+                    None => return Err("Missing value for --webhook".to_owned()),
+                }
+            } else if let Some(event) = hooks::Hooks::flag_event(&arg) {
+                match args.next() {
+                    Some(command) => hooks.set(event, command),
+                    None => return Err(format!("Missing command for {}", arg)),
+                }
+            } else if arg == "--no-hooks" {
+                no_hooks = true;
+            } else if arg == "--rpc" {
+                command = Command::Rpc;
+            } else if arg == "serve" {
+                command = Command::Serve { port: DEFAULT_PORT };
+            } else if let Some(action) = Self::parse_action(&arg, &mut args)? {
+                if !matches!(command, Command::Interactive) {
+                    return Err(format!("Unexpected command \"{}\"", arg));
+                }
+                command = Command::Run(action);
+            } else if arg == "--desc"
+                || arg == "--description"
+                || arg == "--priority"
+                || arg == "--due"
+                || arg == "--tags"
+            {
+                let Command::Run(cli::Action::Add(task)) = &mut command else {
+                    return Err(format!("{} is only valid for add", arg));
+                };
+                let Some(value) = args.next() else {
+                    return Err(format!("Missing value for {}", arg));
+                };
+                match arg.as_str() {
+                    "--priority" => task.priority = value.parse()?,
+                    "--due" => task.due_date = parse_due_date(&value)?,
+                    "--tags" => task.tags = parse_tags(&value),
+                    _ => task.description = value,
+                }
+            } else if arg == "--pending" || arg == "--done" {
+                let Command::Run(cli::Action::List(status)) = &mut command else {
+                    return Err(format!("{} is only valid for list", arg));
+                };
+                *status = Some(if arg == "--done" {
+                    Status::Done
+                } else {
+                    Status::Pending
+                });
+            } else if arg == "--port" {
+                let Command::Serve { port } = &mut command else {
+                    return Err("--port is only valid for serve".to_owned());
+                };
+                *port = match args.next().map(|value| value.parse()) {
+                    Some(Ok(value)) => value,
+                    _ => return Err("Expected a port number after --port".to_owned()),
+                };
+            } else {
+                return Err(format!("Unknown argument \"{}\"", arg));
+            }
+        }
+
+        Ok(Self {
+            file,
+            command,
+            backup_policy,
+            read_only,
+            recent_limit,
+            autosave,
+            webhook,
+            hooks,
+            no_hooks,
+        })
+    }
+
+    /// Parses a one-shot command and its required argument; `None` if `arg` isn't one.
+    fn parse_action(
+        arg: &str,
+        args: &mut impl Iterator<Item = String>,
+    ) -> Result<Option<cli::Action>, String> {
+        if arg == "list" {
+            return Ok(Some(cli::Action::List(None)));
+        }
+        if !matches!(arg, "add" | "find" | "done" | "rm") {
+            return Ok(None);
+        }
+
+        let value = match args.next() {
+            Some(value) if !value.starts_with("--") => value,
+            _ => return Err(format!("Missing task name for {}", arg)),
+        };
+        Ok(Some(match arg {
+            "add" if value.trim().is_empty() => {
+                return Err("Task name must not be empty".to_owned())
+            }
+            "add" => cli::Action::Add(Task::new(value, String::new(), Priority::Low)),
+            "find" => cli::Action::Find(value),
+            "done" => cli::Action::Done(value),
+            _ => cli::Action::Remove(value),
+        }))
+    }
+
+    fn usage() -> &'static str {
+        "Usage: todocli [--file <path>] [--read-only] [--no-autosave] [--recent <n>] [--max-backups <n>] [--strict-backups]
+               [--webhook <url>] [--on-add <cmd>] [--on-complete <cmd>] [--on-remove <cmd>] [--no-hooks]
+               [--rpc | serve [--port <port>]]
+       todocli [--file <path>] add <name> [--desc <text>] [--priority <low|medium|high>] [--due <date>]
+               [--tags <a,b>]
+       todocli [--file <path>] list [--pending | --done]
+       todocli [--file <path>] find <query> | done <name> | rm <name>"
+    }
+}
+
+const OLDEST_TASKS_LIMIT: usize = 10;
+
+struct ConsoleManager {
+    tasks_manager: TasksManager,
+    data_file: DataFile,
+    read_only: bool,
+    /// Names of tasks shown via "Find task" this session, most recent first.
+    viewed: VecDeque<(String, DateTime<Local>)>,
+    recent_limit: usize,
+    search_history: SearchHistory,
+    /// Save to the open file after every command that changed the tasks.
+    autosave: bool,
+    /// Set while the save target must only be written by an explicit save: after a
+    /// failed load, when writing would replace tasks never read, or a backup restore.
+    autosave_paused: bool,
+    /// File an autosave has already backed up; later autosaves to it skip the backup.
+    autosave_backup: Option<String>,
+    running: bool,
+    menu_options: Vec<String>,
+}
+
+impl ConsoleManager {
+    fn new(
+        data_file: DataFile,
+        backup_policy: BackupPolicy,
+        read_only: bool,
+        recent_limit: usize,
+        autosave: bool,
+        webhook: Option<webhook::Webhook>,
+        hooks: hooks::Hooks,
+    ) -> Self {
+        let mut tasks_manager = TasksManager::new();
+        tasks_manager.backup_policy = backup_policy;
+        tasks_manager.webhook = webhook;
+        tasks_manager.hooks = hooks;
+        let mut autosave_paused = false;
+        if Path::new(&data_file.path).exists() {
+            match tasks_manager.read_from_file(&data_file.path) {
+                Ok(_) => println!("Loaded tasks from \"{}\"", data_file.path),
+                Err(msg) => {
+                    println!("{}", msg);
+                    println!(
+                        "Autosave is paused so \"{}\" isn't overwritten, save explicitly to replace it",
+                        data_file.path
+                    );
+                    autosave_paused = true;
+                }
+            }
+        } else {
+            println!(
+                "File \"{}\" doesn't exist yet, starting with an empty list",
+                data_file.path
+            );
+        }
+
+        Self {
+            tasks_manager,
+            search_history: SearchHistory::for_data_file(&data_file.path),
+            data_file,
+            read_only,
+            viewed: VecDeque::new(),
+            recent_limit,
+            autosave,
+            autosave_paused,
+            autosave_backup: None,
+            running: true,
+            menu_options: vec![
+                "Add task".to_owned(),
+                "Find task".to_owned(),
+                "Edit task".to_owned(),
+                "Remove task".to_owned(),
+                "Print tasks".to_owned(),
+                "Store tasks to file".to_owned(),
+                "Read tasks from file".to_owned(),
+                "Show config".to_owned(),
+                "Email tasks".to_owned(),
+                "Pomodoro".to_owned(),
+                "List backups".to_owned(),
+                "Restore backup".to_owned(),
+                "Manage files".to_owned(),
+                "Status".to_owned(),
+                "Toggle read-only mode".to_owned(),
+                "Export text report".to_owned(),
+                "Activity".to_owned(),
+                "Review oldest tasks".to_owned(),
+                "Recent activity".to_owned(),
+                "Search history".to_owned(),
+                "Review changes".to_owned(),
+                "Mark task done".to_owned(),
+                "Quit".to_owned(),
+                "Sort by due date".to_owned(),
+                "Sort tasks".to_owned(),
+                "Print tasks by priority".to_owned(),
+                "Export tasks".to_owned(),
+                "Import tasks".to_owned(),
+                "Undo last change".to_owned(),
+                "Print tasks by tag".to_owned(),
+                "List tags".to_owned(),
+                "Test webhook".to_owned(),
+            ],
+        }
+    }
+
+    fn print_menu(&self) {
+        for (index, menu_option) in self.menu_options.iter().enumerate() {
+            println!("{}. {}", index + 1, menu_option);
+        }
+    }
+
+    fn input(query: &str) -> std::io::Result<String> {
+        print!("{}", query);
+        std::io::stdout().flush()?;
+
+        let mut buffer = String::new();
+        if std::io::stdin().read_line(&mut buffer)? == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(buffer.trim().to_owned())
+    }
+
+    /// The file saves go to: the last one read or written, else the data file.
+    fn save_target(&self) -> String {
+        match self.tasks_manager.active_file() {
+            Some(path) => path.to_owned(),
+            None => self.data_file.path.clone(),
+        }
+    }
+
+    /// Saves after a command that changed the tasks; a failed write keeps them in memory.
+    ///
+    /// `changes_before` is the unsaved change count from before the command, so a
+    /// failed save is retried on the next change rather than after every command.
+    fn autosave(&mut self, changes_before: usize) {
+        if !self.autosave
+            || self.autosave_paused
+            || self.read_only
+            || !self.running
+            || self.tasks_manager.unsaved_changes() <= changes_before
+        {
+            return;
+        }
+
+        // One backup per file and session, so autosaves don't push out older backups.
+        let target = self.save_target();
+        let saved = if self.autosave_backup.as_deref() == Some(target.as_str()) {
+            self.tasks_manager.store_without_backup(&target)
+        } else {
+            self.tasks_manager
+                .store_to_file(&target, SaveMode::Overwrite)
+        };
+        match saved {
+            Ok(_) => self.autosave_backup = Some(target),
+            Err(msg) => println!("Autosave failed, changes are kept in memory: {}", msg),
+        }
+    }
+
+    /// Saves unsaved changes and stops the menu loop, asking first if saving fails.
+    ///
+    /// With autosave off or paused it asks before saving, showing what changed.
+    fn quit(&mut self) {
+        if self.tasks_manager.is_dirty() {
+            if !self.read_only && (self.autosave_paused || !self.autosave) {
+                // A paused autosave guards the file, so saving must be asked for.
+                let (choices, default) = if self.autosave_paused {
+                    ("y/N", false)
+                } else {
+                    ("Y/n", true)
+                };
+                let prompt = format!(
+                    "{} — save to \"{}\" before quitting? [{}]: ",
+                    self.tasks_manager.changes_since_save().summary(),
+                    self.save_target(),
+                    choices
+                );
+                let save = match Self::input(&prompt) {
+                    Ok(answer) if answer.is_empty() => default,
+                    Ok(answer) => answer.eq_ignore_ascii_case("y"),
+                    Err(_) => false,
+                };
+                if !save {
+                    println!("Quitting without saving");
+                    self.running = false;
+                    return;
+                }
+            }
+
+            let saved = if self.read_only {
+                Err("Read-only mode: unsaved changes can't be saved".to_owned())
+            } else {
+                let target = self.save_target();
+                self.tasks_manager
+                    .store_to_file(&target, SaveMode::Overwrite)
+            };
+
+            match saved {
+                Ok(msg) => println!("{}", msg),
+                Err(msg) => {
+                    println!("{}", msg);
+                    // Without input there is no way to retry, so quit regardless.
+                    if let Ok(answer) = Self::input("Quit without saving? [y/N]: ") {
+                        if !answer.eq_ignore_ascii_case("y") {
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+
+        self.running = false;
+    }
+
+    /// Asks for a task through the fuzzy picker, which matches names and the start of
+    /// descriptions; a choice is an index into the tasks, `None` means cancelled.
+    fn pick_task(&self, query: &str) -> std::io::Result<Option<picker::Pick>> {
+        let candidates: Vec<String> = self
+            .tasks_manager
+            .tasks
+            .iter()
+            .map(|task| match task.description.trim() {
+                "" => task.name.clone(),
+                description => format!(
+                    "{} — {}",
+                    task.name,
+                    table::truncate(&table::single_line(description), PICKER_DESCRIPTION_WIDTH)
+                ),
+            })
+            .collect();
+        picker::pick(query, &candidates)
+    }
+
+    /// Asks for a file name, falling back to the active data file on empty input.
+    fn input_filename(&self, query: &str) -> std::io::Result<String> {
+        let filename = Self::input(&format!("{} [{}]: ", query, self.data_file.path))?;
+        if filename.is_empty() {
+            Ok(self.data_file.path.clone())
+        } else {
+            Ok(filename)
+        }
+    }
+
+    /// Writes the chosen tasks to an .eml file, as a digest when there are several.
+    fn email_tasks(&self) -> Result<String, String> {
+        let input = |query: &str| {
+            Self::input(query).map_err(|err| format!("Error geting user input {}", err))
+        };
+
+        let names = input("Enter task names to email (comma separated): ")?;
+        let mut tasks = vec![];
+        for name in names
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            match self.tasks_manager.lookup(name) {
+                Some(index) => tasks.push(&self.tasks_manager.tasks[index]),
+                None => return Err(format!("Task with name \"{}\" doesn't exist", name)),
+            }
+        }
+        if tasks.is_empty() {
+            return Err("No tasks selected".to_owned());
+        }
+
+        let from = input("From: ")?;
+        let to = input("To: ")?;
+        let filename = input("Enter file name to write (.eml): ")?;
+
+        let message = email::compose(&tasks, &from, &to, Local::now());
+        match std::fs::write(&filename, message) {
+            Ok(_) => Ok(format!("Email written to \"{}\"", filename)),
+            Err(err) => Err(format!("Error writing file \"{}\": {}", filename, err)),
+        }
+    }
+
+    /// Runs a focus countdown on a task and logs it when it completes.
+    fn pomodoro(&mut self) -> Result<String, String> {
+        let input = |query: &str| {
+            Self::input(query).map_err(|err| format!("Error geting user input {}", err))
+        };
+
+        let name = input("Enter task name to focus on: ")?;
+        if self.tasks_manager.lookup(&name).is_none() {
+            return Err(format!("Task with name \"{}\" doesn't exist", name));
+        }
+
+        let minutes = input(&format!("Minutes [{}]: ", pomodoro::DEFAULT_WORK_MINUTES))?;
+        let minutes = match minutes.as_str() {
+            "" => pomodoro::DEFAULT_WORK_MINUTES,
+            minutes => match minutes.parse() {
+                Ok(minutes) if (1..=pomodoro::MAX_MINUTES).contains(&minutes) => minutes,
+                _ => {
+                    return Err(format!(
+                        "\"{}\" is not a valid number of minutes, expected 1 to {}",
+                        minutes,
+                        pomodoro::MAX_MINUTES
+                    ))
+                }
+            },
+        };
+
+        let mut reader = pomodoro::LineReader::new();
+        if let pomodoro::Outcome::Cancelled = pomodoro::countdown(&name, minutes, &mut reader) {
+            reader.finish();
+            return Ok("Pomodoro cancelled".to_owned());
+        }
+
+        let msg = self.tasks_manager.log_pomodoro(&name)?;
+        println!("{}", msg);
+
+        print!("Take a {} minute break? [y/N]: ", pomodoro::BREAK_MINUTES);
+        let _ = std::io::stdout().flush();
+        if reader.read_line().eq_ignore_ascii_case("y") {
+            match pomodoro::countdown("Break", pomodoro::BREAK_MINUTES, &mut reader) {
+                pomodoro::Outcome::Finished => println!("Break is over"),
+                pomodoro::Outcome::Cancelled => println!("Break cancelled"),
+            }
+            reader.finish();
+        }
+
+        Ok("Pomodoro finished".to_owned())
+    }
+
+    fn export_text_report(&self) -> Result<String, String> {
+        let input = |query: &str| {
+            Self::input(query).map_err(|err| format!("Error geting user input {}", err))
+        };
+
+        let width = input(&format!("Line width [{}]: ", report::DEFAULT_WIDTH))?;
+        let width = match width.as_str() {
+            "" => report::DEFAULT_WIDTH,
+            width => match width.parse() {
+                Ok(width) if width >= report::MIN_WIDTH => width,
+                _ => {
+                    return Err(format!(
+                        "Line width must be a number of at least {}",
+                        report::MIN_WIDTH
+                    ))
+                }
+            },
+        };
+        let filename = input("Enter file name to write: ")?;
+
+        let text = report::text_report(&self.tasks_manager.tasks, width, Local::now());
+        match std::fs::write(&filename, text) {
+            Ok(_) => Ok(format!("Report written to \"{}\"", filename)),
+            Err(err) => Err(format!("Error writing file \"{}\": {}", filename, err)),
+        }
+    }
+
+    fn export_tasks(&self) -> Result<String, String> {
+        let input = |query: &str| {
+            Self::input(query).map_err(|err| format!("Error geting user input {}", err))
+        };
+
+        let format = input("Export as [c]sv or [m]arkdown? ")?.to_lowercase();
+        let format = match format.as_str() {
+            "c" | "csv" => ExportFormat::Csv,
+            "m" | "md" | "markdown" => ExportFormat::Markdown,
+            _ => return Err(format!("I don't understand \"{}\"", format)),
+        };
+        let filename = input("Enter file name to write: ")?;
+
+        self.tasks_manager.export(&filename, format)
+    }
+
+    fn print_activity(&self) {
+        let days = match Self::input(&format!("Days [{}]: ", report::DEFAULT_ACTIVITY_DAYS)) {
+            Ok(days) => days,
+            Err(err) => {
+                println!("Error geting user input {}", err);
+                return;
+            }
+        };
+        let days = match days.as_str() {
+            "" => report::DEFAULT_ACTIVITY_DAYS,
+            days => match days.parse() {
+                Ok(days) if days > 0 => days,
+                _ => {
+                    println!("\"{}\" is not a valid number of days", days);
+                    return;
+                }
+            },
+        };
+
+        let buckets =
+            report::created_per_day(&self.tasks_manager.tasks, Local::now().date_naive(), days);
+        let rows: Vec<(String, u32)> = buckets
+            .into_iter()
+            .map(|(date, count)| (date.format("%d-%m-%Y").to_string(), count))
+            .collect();
+
+        println!("Tasks created per day:");
+        print!("{}", report::bar_chart(&rows));
+    }
+
+    /// Prompts for each field showing its current value; empty input keeps it.
+    fn edit_task(&mut self, name: &str) -> Result<String, String> {
+        let input = |query: &str| {
+            Self::input(query).map_err(|err| format!("Error geting user input {}", err))
+        };
+        let Some(index) = self.tasks_manager.lookup(name) else {
+            return Err(format!("Task with name \"{}\" doesn't exist", name));
+        };
+        let task = &self.tasks_manager.tasks[index];
+        let mut patch = TaskPatch::default();
+
+        loop {
+            let new_name = input(&format!("Name [{}]: ", task.name))?;
+            if new_name.is_empty() || new_name == task.name {
+                break;
+            }
+            if self.tasks_manager.find_task(&new_name).is_some() {
+                println!("Task with name \"{}\" already exists", new_name);
+                continue;
+            }
+            patch.name = Some(new_name);
+            break;
+        }
+
+        let description = input(&format!("Description [{}]: ", task.description))?;
+        if !description.is_empty() {
+            patch.description = Some(description);
+        }
+
+        loop {
+            let priority = input(&format!("Priority [{}]: ", task.priority))?;
+            if priority.is_empty() {
+                break;
+            }
+            match priority.parse() {
+                Ok(priority) => {
+                    patch.priority = Some(priority);
+                    break;
+                }
+                Err(msg) => println!("{}", msg),
+            }
+        }
+
+        let current_due = match task.due_date {
+            Some(due_date) => due_date.format("%Y-%m-%d %H:%M").to_string(),
+            None => "none".to_owned(),
+        };
+        loop {
+            let due_date = input(&format!("Due date, - to clear [{}]: ", current_due))?;
+            if due_date.is_empty() {
+                break;
+            }
+            if due_date == "-" {
+                patch.due_date = Some(None);
+                break;
+            }
+            match parse_due_date(&due_date) {
+                Ok(due_date) => {
+                    patch.due_date = Some(due_date);
+                    break;
+                }
+                Err(msg) => println!("{}", msg),
+            }
+        }
+
+        loop {
+            let tags = input(&format!(
+                "Tags, +tag adds, -tag removes, - clears [{}]: ",
+                task.tags.join(", ")
+            ))?;
+            match Self::edit_tags(&task.tags, &tags) {
+                Ok(tags) => {
+                    patch.tags = tags;
+                    break;
+                }
+                Err(msg) => println!("{}", msg),
+            }
+        }
+
+        if patch.is_empty() {
+            return Ok("Nothing changed".to_owned());
+        }
+        self.tasks_manager.update_task(name, patch)
+    }
+
+    /// Applies a tag edit: blank keeps the tags, `-` clears them, `+tag`/`-tag` entries
+    /// add and remove, and plain tags replace the list.
+    ///
+    /// Mixing `+tag`/`-tag` with plain tags is an error rather than a tag named "+tag".
+    fn edit_tags(current: &[String], input: &str) -> Result<Option<Vec<String>>, String> {
+        let entries: Vec<&str> = input
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .collect();
+        if entries.is_empty() {
+            return Ok(None);
+        }
+        if entries == ["-"] {
+            return Ok(Some(vec![]));
+        }
+        let signed = entries
+            .iter()
+            .filter(|entry| entry.starts_with('+') || entry.starts_with('-'))
+            .count();
+        if signed == 0 {
+            return Ok(Some(normalize_tags(entries)));
+        }
+        if signed < entries.len() {
+            return Err(
+                "Use either +tag/-tag entries or a plain list of tags, not both".to_owned(),
+            );
+        }
+
+        let mut tags = current.to_vec();
+        for entry in entries {
+            let (sign, tag) = entry.split_at(1);
+            let tag = tag.trim().to_lowercase();
+            if sign == "+" {
+                tags.push(tag);
+            } else {
+                tags.retain(|existing| *existing != tag);
+            }
+        }
+        Ok(Some(normalize_tags(tags.iter().map(String::as_str))))
+    }
+
+    fn print_tasks_by_tag(&self) {
+        let tag = match Self::input("Enter tag: ") {
+            Ok(tag) => tag,
+            Err(err) => {
+                println!("Error geting user input {}", err);
+                return;
+            }
+        };
+
+        let tasks = self.tasks_manager.filter_by_tag(&tag);
+        if tasks.is_empty() {
+            println!("No tasks tagged \"{}\"", tag);
+            return;
+        }
+        table::print(&tasks);
+    }
+
+    fn print_tags(&self) {
+        let tags = self.tasks_manager.list_tags();
+        if tags.is_empty() {
+            println!("No tags yet");
+            return;
+        }
+        for (tag, count) in tags {
+            println!("{} ({})", tag, count);
+        }
+    }
+
+    /// Asks for a sort key and direction, then reorders the tasks.
+    fn sort_tasks(&mut self) -> Result<(), String> {
+        let input =
+            |query| Self::input(query).map_err(|err| format!("Error geting user input {}", err));
+
+        let key = input("Sort by [n]ame, [p]riority or [a]dded time? ")?.to_lowercase();
+        let by = match key.as_str() {
+            "n" => SortKey::Name,
+            "p" => SortKey::Priority,
+            "a" => SortKey::AddTime,
+            _ => return Err(format!("I don't understand \"{}\"", key)),
+        };
+        let descending = match input("[a]scending or [d]escending? [a]: ")?
+            .to_lowercase()
+            .as_str()
+        {
+            "" | "a" => false,
+            "d" => true,
+            direction => return Err(format!("I don't understand \"{}\"", direction)),
+        };
+
+        self.tasks_manager.sort_tasks(by, descending);
+        Ok(())
+    }
+
+    fn print_tasks_by_priority(&self) {
+        let priority = match Self::input("Show which priority? [h]igh, [m]edium or [l]ow: ") {
+            Ok(priority) => priority.to_lowercase(),
+            Err(err) => {
+                println!("Error geting user input {}", err);
+                return;
+            }
+        };
+        let priority = match priority.as_str() {
+            "h" => Priority::High,
+            "m" => Priority::Medium,
+            "l" => Priority::Low,
+            other => match other.parse() {
+                Ok(priority) => priority,
+                Err(msg) => {
+                    println!("{}", msg);
+                    return;
+                }
+            },
+        };
+
+        let tasks = self.tasks_manager.filter_by_priority(priority);
+        if tasks.is_empty() {
+            println!("No tasks with that priority");
+            return;
+        }
+        table::print(&tasks);
+    }
+
+    /// Walks the oldest tasks one by one, offering to remove or promote each.
+    fn review_oldest_tasks(&mut self) {
+        let now = Local::now();
+        let oldest: Vec<(String, i64)> = self
+            .tasks_manager
+            .oldest_tasks(OLDEST_TASKS_LIMIT)
+            .into_iter()
+            .map(|index| {
+                let task = &self.tasks_manager.tasks[index];
+                (task.name.clone(), (now - task.add_time).num_days())
+            })
+            .collect();
+
+        if oldest.is_empty() {
+            println!("No tasks to review");
+            return;
+        }
+
+        for (name, age) in oldest {
+            println!("\"{}\" has been waiting for {} days", name, age);
+            let action = match Self::input("[d]o nothing, [r]emove, [p]romote priority, [q]uit: ") {
+                Ok(action) => action.to_lowercase(),
+                Err(err) => {
+                    println!("Error geting user input {}", err);
+                    return;
+                }
+            };
+
+            let result = match action.as_str() {
+                "" | "d" => continue,
+                "r" => self.tasks_manager.remove_task(&name),
+                "p" => self.tasks_manager.promote_task(&name),
+                "q" => return,
+                _ => Err(format!(
+                    "I don't understand \"{}\", leaving it as is",
+                    action
+                )),
+            };
+            match result {
+                Ok(msg) => println!("{}", msg),
+                Err(msg) => println!("{}", msg),
+            }
+        }
+    }
+
+    /// Shows the `choice` made in the picker, or else the task `name` finds.
+    fn find(&mut self, name: String, choice: Option<usize>) {
+        if name.is_empty() && choice.is_none() {
+            println!("Cancelled");
+            return;
+        }
+        if let Err(msg) = self.search_history.record(&name) {
+            println!("{}", msg);
+        }
+
+        let index = match choice.or_else(|| self.tasks_manager.lookup(&name)) {
+            Some(index) => index,
+            None => {
+                let matches = self.tasks_manager.search_tasks(&name);
+                match matches.as_slice() {
+                    [] => {
+                        println!("No task matches \"{}\"", name);
+                        return;
+                    }
+                    [index] => *index,
+                    _ => match self.choose_match(&matches) {
+                        Ok(Some(index)) => index,
+                        Ok(None) => return,
+                        Err(msg) => {
+                            println!("{}", msg);
+                            return;
+                        }
+                    },
+                }
+            }
+        };
+
+        let task = &self.tasks_manager.tasks[index];
+        task.print_task();
+        self.record_view(task.name.clone());
+    }
+
+    /// Lists the matching tasks by number and asks for one; blank input cancels.
+    fn choose_match(&self, matches: &[usize]) -> Result<Option<usize>, String> {
+        if matches.is_empty() {
+            return Ok(None);
+        }
+        for (number, &index) in matches.iter().enumerate() {
+            println!("{}. {}", number + 1, self.tasks_manager.tasks[index].name);
+        }
+
+        let choice = Self::input("Enter task number (blank to cancel): ")
+            .map_err(|err| format!("Error geting user input {}", err))?;
+        if choice.is_empty() {
+            return Ok(None);
+        }
+        match choice
+            .parse::<usize>()
+            .ok()
+            .and_then(|number| number.checked_sub(1))
+            .and_then(|number| matches.get(number))
+        {
+            Some(&index) => Ok(Some(index)),
+            None => Err(format!("\"{}\" is not a listed task", choice)),
+        }
+    }
+
+    /// Asks for a task and resolves ids and partial names, returning its `#id` or `None`
+    /// when cancelled or unmatched.
+    fn select_task(&self, query: &str) -> Option<String> {
+        let (name, choice) = match self.pick_task(query) {
+            Ok(Some(pick)) => (pick.query, pick.choice),
+            Ok(None) => return None,
+            Err(err) => {
+                println!("Error geting user input {}", err);
+                return None;
+            }
+        };
+        // A blank line would otherwise search for everything.
+        if name.is_empty() && choice.is_none() {
+            println!("Cancelled");
+            return None;
+        }
+        if let Some(index) = choice.or_else(|| self.tasks_manager.lookup(&name)) {
+            return Some(format!("#{}", self.tasks_manager.tasks[index].id));
+        }
+
+        let matches = self.tasks_manager.search_tasks(&name);
+        let index = match matches.as_slice() {
+            [] => {
+                println!("No task matches \"{}\"", name);
+                return None;
+            }
+            [index] => {
+                let candidate = &self.tasks_manager.tasks[*index].name;
+                match Self::input(&format!("Did you mean \"{}\"? [Y/n]: ", candidate)) {
+                    Ok(answer) if answer.eq_ignore_ascii_case("n") => return None,
+                    Ok(_) => *index,
+                    Err(err) => {
+                        println!("Error geting user input {}", err);
+                        return None;
+                    }
+                }
+            }
+            _ => {
+                println!("\"{}\" matches several tasks:", name);
+                match self.choose_match(&matches) {
+                    Ok(Some(index)) => index,
+                    Ok(None) => return None,
+                    Err(msg) => {
+                        println!("{}", msg);
+                        return None;
+                    }
+                }
+            }
+        };
+        Some(format!("#{}", self.tasks_manager.tasks[index].id))
+    }
+
+    fn rerun_search(&mut self) {
+        if self.search_history.entries().is_empty() {
+            println!("No searches yet");
+            return;
+        }
+
+        for (index, query) in self.search_history.entries().iter().enumerate() {
+            println!("{}. {}", index + 1, query);
+        }
+
+        let choice = match Self::input("Enter search number to rerun: ") {
+            Ok(choice) => choice,
+            Err(err) => {
+                println!("Error geting user input {}", err);
+                return;
+            }
+        };
+        match choice
+            .parse()
+            .ok()
+            .and_then(|number| self.search_history.get(number))
+        {
+            Some(query) => self.find(query.to_owned(), None),
+            None => println!("\"{}\" is not a listed search", choice),
+        }
+    }
+
+    fn review_changes(&self) {
+        let diff = self.tasks_manager.changes_since_save();
+        if diff.is_empty() {
+            println!("No changes since the last save");
+            return;
+        }
+
+        println!("{}", diff.summary());
+        for task in &diff.added {
+            println!("+ {}", task.name);
+        }
+        for task in &diff.removed {
+            println!("- {}", task.name);
+        }
+        for edited in &diff.edited {
+            println!("~ {}", edited.task.name);
+            for change in &edited.changes {
+                println!(
+                    "    {}: \"{}\" -> \"{}\"",
+                    change.field, change.before, change.after
+                );
+            }
+        }
+    }
+
+    fn record_view(&mut self, name: String) {
+        self.viewed.retain(|(viewed, _)| *viewed != name);
+        self.viewed.push_front((name, Local::now()));
+        self.viewed.truncate(self.recent_limit);
+    }
+
+    fn print_recent_activity(&self) {
+        let now = Local::now();
+
+        println!("Recently modified:");
+        let modified = self.tasks_manager.recently_modified(self.recent_limit);
+        if modified.is_empty() {
+            println!("  (no tasks yet)");
+        }
+        for index in modified {
+            let task = &self.tasks_manager.tasks[index];
+            println!("  {} ({})", task.name, time_ago(task.last_modified(), now));
+        }
+
+        println!("Recently viewed:");
+        if self.viewed.is_empty() {
+            println!("  (nothing viewed this session)");
+        }
+        for (name, time) in &self.viewed {
+            println!("  {} ({})", name, time_ago(*time, now));
+        }
+    }
+
+    fn print_status(&self) {
+        let tasks_manager = &self.tasks_manager;
+        match tasks_manager.active_file() {
+            Some(path) => println!("File: {}", path),
+            None => println!("File: no file — in-memory only"),
+        }
+
+        if tasks_manager.is_dirty() {
+            println!(
+                "Unsaved changes: yes ({} since last save)",
+                tasks_manager.unsaved_changes()
+            );
+        } else {
+            println!("Unsaved changes: no");
+        }
+
+        println!("Tasks: {}", tasks_manager.tasks.len());
+        match tasks_manager.last_saved() {
+            Some(time) => println!("Last written: {}", time.format("%d-%m-%Y %H:%M:%S")),
+            None => println!("Last written: never"),
+        }
+    }
+
+    fn list_backups(&self) {
+        let target = self.save_target();
+        let backups = match backup::list_backups(&target) {
+            Ok(backups) => backups,
+            Err(msg) => {
+                println!("{}", msg);
+                return;
+            }
+        };
+
+        if backups.is_empty() {
+            println!("No backups of \"{}\"", target);
+            return;
+        }
+
+        for backup in backups {
+            let modified = match backup.modified {
+                Some(time) => time.format("%d-%m-%Y %H:%M:%S").to_string(),
+                None => "unknown".to_owned(),
+            };
+            println!(
+                "{} | {} bytes | {}",
+                backup.path.display(),
+                backup.size,
+                modified
+            );
+        }
+    }
+
+    /// Lets the user pick a backup of the file saves go to and load it as the working list.
+    fn restore_backup(&mut self) -> Result<String, String> {
+        let target = self.save_target();
+        let mut candidates = vec![];
+        for backup in backup::list_backups(&target)? {
+            match TasksManager::read_tasks(&backup.path) {
+                Ok(tasks) => candidates.push((backup, tasks)),
+                Err(msg) => println!("Skipping \"{}\": {}", backup.path.display(), msg),
+            }
+        }
+        if candidates.is_empty() {
+            return Err(format!("No backups of \"{}\"", target));
+        }
+
+        for (index, (backup, tasks)) in candidates.iter().enumerate() {
+            let modified = match backup.modified {
+                Some(time) => time.format("%d-%m-%Y %H:%M:%S").to_string(),
+                None => "unknown".to_owned(),
+            };
+            println!(
+                "{}. {} | {} tasks | {} bytes",
+                index + 1,
+                modified,
+                tasks.len(),
+                backup.size
+            );
+        }
+
+        let choice = Self::input("Enter backup number to restore: ")
+            .map_err(|err| format!("Error geting user input {}", err))?;
+        let (backup, tasks) = match choice.parse::<usize>() {
+            Ok(number) if number >= 1 && number <= candidates.len() => &candidates[number - 1],
+            _ => return Err(format!("\"{}\" is not a listed backup", choice)),
+        };
+
+        let current = self.tasks_manager.tasks.len();
+        println!(
+            "Backup has {} tasks, current list has {} ({:+})",
+            tasks.len(),
+            current,
+            tasks.len() as i64 - current as i64
+        );
+        let confirm = Self::input("Replace the current list with this backup? [y/N]: ")
+            .map_err(|err| format!("Error geting user input {}", err))?;
+        if !confirm.eq_ignore_ascii_case("y") {
+            return Ok("Restore cancelled".to_owned());
+        }
+
+        self.tasks_manager.restore_backup(&backup.path, &target)
+    }
+
+    fn test_webhook(&self) -> Result<String, String> {
+        match &self.tasks_manager.webhook {
+            Some(webhook) => webhook.ping(),
+            None => Err(format!(
+                "No webhook set, pass --webhook <url> or set {}",
+                webhook::URL_VAR
+            )),
+        }
+    }
+
+    /// Lists the task files next to the active data file and opens, renames or deletes one.
+    fn manage_files(&mut self) -> Result<String, String> {
+        let dir = match Path::new(&self.data_file.path).parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+
+        let entries = std::fs::read_dir(&dir)
+            .map_err(|err| format!("Error reading \"{}\": {}", dir.display(), err))?;
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        files.sort();
+
+        if files.is_empty() {
+            return Err(format!("No task files in \"{}\"", dir.display()));
+        }
+
+        for (index, path) in files.iter().enumerate() {
+            let count = match TasksManager::read_tasks(path) {
+                Ok(tasks) => format!("{} tasks", tasks.len()),
+                Err(_) => "(unreadable)".to_owned(),
+            };
+            let modified = match path.metadata().and_then(|metadata| metadata.modified()) {
+                Ok(time) => DateTime::<Local>::from(time)
+                    .format("%d-%m-%Y %H:%M:%S")
+                    .to_string(),
+                Err(_) => "unknown".to_owned(),
+            };
+            println!(
+                "{}. {} | {} | {}",
+                index + 1,
+                path.display(),
+                count,
+                modified
+            );
+        }
+
+        let input = |query: &str| {
+            Self::input(query).map_err(|err| format!("Error geting user input {}", err))
+        };
+
+        let choice = input("Enter file number: ")?;
+        let path = match choice.parse::<usize>() {
+            Ok(number) if number >= 1 && number <= files.len() => &files[number - 1],
+            _ => return Err(format!("\"{}\" is not a listed file", choice)),
+        };
+        let filename = path.to_string_lossy().into_owned();
+        let is_active = Path::new(&self.data_file.path) == path.as_path();
+
+        match input("[o]pen, [r]ename or [d]elete: ")?
+            .to_lowercase()
+            .as_str()
+        {
+            "o" | "open" => {
+                let msg = self.tasks_manager.read_from_file(&filename)?;
+                self.data_file = DataFile {
+                    path: filename,
+                    source: PathSource::Opened,
+                };
+                Ok(msg)
+            }
+            "r" | "rename" => {
+                let new_name = input("Enter new file name: ")?;
+                if new_name.is_empty() {
+                    return Err("File name must not be empty".to_owned());
+                }
+                let target = dir.join(&new_name);
+                if target.exists() {
+                    return Err(format!("File \"{}\" already exists", target.display()));
+                }
+                std::fs::rename(path, &target)
+                    .map_err(|err| format!("Error renaming \"{}\": {}", filename, err))?;
+                let target_name = target.to_string_lossy().into_owned();
+                if self.tasks_manager.active_file() == Some(filename.as_str()) {
+                    self.tasks_manager.active_file = Some(target_name.clone());
+                }
+                if is_active {
+                    self.data_file.path = target_name;
+                }
+                Ok(format!(
+                    "Renamed \"{}\" to \"{}\"",
+                    filename,
+                    target.display()
+                ))
+            }
+            "d" | "delete" => {
+                let confirm = input(&format!("Delete \"{}\"? [y/N]: ", filename))?;
+                if !confirm.eq_ignore_ascii_case("y") {
+                    return Ok("Delete cancelled".to_owned());
+                }
+                std::fs::remove_file(path)
+                    .map_err(|err| format!("Error deleting \"{}\": {}", filename, err))?;
+                Ok(format!("Deleted \"{}\"", filename))
+            }
+            action => Err(format!("I don't understand \"{}\"", action)),
+        }
+    }
+
+    /// Menu commands refused in read-only mode.
+    fn is_mutating(command: &str) -> bool {
+        matches!(
+            command,
+            // Add, edit, remove, store, pomodoro, restore backup, manage files, review oldest,
+            // mark done, sort by due date, sort, import, undo
+            "1" | "3" | "4" | "6" | "10" | "12" | "13" | "18" | "22" | "24" | "25" | "28" | "29"
+        )
+    }
+
+    fn process_command(&mut self) {
+        match Self::input("\nEnter command index: ") {
+            Ok(command) => self.run_command(&command),
+            Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => self.quit(),
+            Err(err) => println!("Error geting user input {err}"),
+        }
+    }
+
+    fn run_command(&mut self, command: &str) {
+        match command {
+            command if self.read_only && Self::is_mutating(command) => {
+                println!("Read-only mode: this command would change the tasks or files")
+            }
+            "1" => {
+                let mut task = match Task::new_from_console() {
+                    Ok(task) => task,
+                    Err(err) => {
+                        println!("Error geting user input {}", err);
+                        return;
+                    }
+                };
+                while self.tasks_manager.find_task(&task.name).is_some() {
+                    let prompt = format!(
+                        "Task \"{}\" already exists, enter a different name: ",
+                        task.name
+                    );
+                    match Self::input(&prompt) {
+                        Ok(name) => task.name = name,
+                        Err(err) => {
+                            println!("Error geting user input {}", err);
+                            return;
+                        }
+                    }
+                }
+
+                match self.tasks_manager.add_task(task) {
+                    Ok(msg) => println!("{}", msg),
+                    Err(msg) => println!("{}", msg),
+                }
+            }
+            "2" => {
+                let pick = match self.pick_task("Enter task name to find (!n reruns search n): ") {
+                    Ok(Some(pick)) => pick,
+                    Ok(None) => return,
+                    Err(err) => {
+                        println!("Error geting user input {}", err);
+                        return;
+                    }
+                };
+
+                match pick.query.strip_prefix('!').map(str::parse::<usize>) {
+                    Some(Ok(number)) => match self.search_history.get(number) {
+                        Some(query) => self.find(query.to_owned(), None),
+                        None => println!("No search history entry {}", number),
+                    },
+                    _ => self.find(pick.query, pick.choice),
+                }
+            }
+            "3" => {
+                let Some(name) = self.select_task("Enter task name to edit: ") else {
+                    return;
+                };
+
+                match self.edit_task(&name) {
+                    Ok(msg) => println!("{}", msg),
+                    Err(msg) => println!("{}", msg),
+                }
+            }
+            "4" => {
+                let Some(name) = self.select_task("Enter task name to remove: ") else {
+                    return;
+                };
+
+                match self.tasks_manager.remove_task(&name) {
+                    Ok(msg) => println!("{}", msg),
+                    Err(msg) => println!("{}", msg),
+                }
+            }
+            "5" => {
+                let status = match Self::input("Show [a]ll, [p]ending or [d]one tasks? [a]: ") {
+                    Ok(filter) => match filter.to_lowercase().as_str() {
+                        "" | "a" => None,
+                        "p" => Some(Status::Pending),
+                        "d" => Some(Status::Done),
+                        _ => {
+                            println!("I don't understand \"{}\"", filter);
+                            return;
+                        }
+                    },
+                    Err(err) => {
+                        println!("Error geting user input {}", err);
+                        return;
+                    }
+                };
+
+                self.tasks_manager.print_tasks(status);
+            }
+            "6" => {
+                let diff = self.tasks_manager.changes_since_save();
+                if !diff.is_empty() {
+                    match Self::input(&format!("{} — save? [Y/n]: ", diff.summary())) {
+                        Ok(answer) if answer.eq_ignore_ascii_case("n") => return,
+                        Ok(_) => {}
+                        Err(err) => {
+                            println!("Error geting user input {}", err);
+                            return;
+                        }
+                    }
+                }
+
+                let filename = match self.input_filename("Enter file name to save") {
+                    Ok(name) => name,
+                    Err(err) => {
+                        println!("Error geting user input {}", err);
+                        return;
+                    }
+                };
+
+                let mode = if Path::new(&filename).exists() {
+                    match Self::input("File exists: [o]verwrite, [m]erge or [c]ancel? ") {
+                        Ok(answer) => match answer.to_lowercase().as_str() {
+                            "o" => SaveMode::Overwrite,
+                            "m" => SaveMode::Merge,
+                            _ => {
+                                println!("Save cancelled");
+                                return;
+                            }
+                        },
+                        Err(err) => {
+                            println!("Error geting user input {}", err);
+                            return;
+                        }
+                    }
+                } else {
+                    SaveMode::FailIfExists
+                };
+
+                match self.tasks_manager.store_to_file(&filename, mode) {
+                    Ok(msg) => {
+                        self.autosave_paused = false;
+                        println!("{}", msg)
+                    }
+                    Err(msg) => println!("{}", msg),
+                }
+            }
+            "7" => {
+                let filename = match self.input_filename("Enter file name to open") {
+                    Ok(name) => name,
+                    Err(err) => {
+                        println!("Error geting user input {}", err);
+                        return;
+                    }
+                };
+
+                match self.tasks_manager.read_from_file(filename.as_str()) {
+                    Ok(msg) => {
+                        self.autosave_paused = false;
+                        println!("{}", msg)
+                    }
+                    Err(msg) => println!("{}", msg),
+                }
+            }
+            "8" => {
+                println!(
+                    "Data file: {} (from {})",
+                    self.data_file.path, self.data_file.source
+                );
+                let autosave = if !self.autosave {
+                    "off"
+                } else if self.autosave_paused {
+                    "paused until the next save"
+                } else {
+                    "on"
+                };
+                println!("Autosave: {}", autosave);
+                match &self.tasks_manager.webhook {
+                    Some(webhook) => println!("Webhook: {}", webhook.url()),
+                    None => println!("Webhook: off"),
+                }
+                let hooks = self.tasks_manager.hooks.configured();
+                if hooks.is_empty() {
+                    println!("Hooks: none");
+                }
+                for (name, command) in hooks {
+                    println!("Hook {}: {}", name, command);
+                }
+            }
+            "9" => match self.email_tasks() {
+                Ok(msg) => println!("{}", msg),
+                Err(msg) => println!("{}", msg),
+            },
+            "10" => match self.pomodoro() {
+                Ok(msg) => println!("{}", msg),
+                Err(msg) => println!("{}", msg),
+            },
+            "11" => self.list_backups(),
+            "12" => match self.restore_backup() {
+                // The restored list only replaces the file through an explicit save.
+                Ok(msg) => {
+                    self.autosave_paused = true;
+                    println!("{}", msg)
+                }
+                Err(msg) => println!("{}", msg),
+            },
+            "13" => match self.manage_files() {
+                Ok(msg) => println!("{}", msg),
+                Err(msg) => println!("{}", msg),
+            },
+            "14" => self.print_status(),
+            "15" => {
+                self.read_only = !self.read_only;
+                if self.read_only {
+                    println!("Read-only mode on");
+                } else {
+                    println!("Read-only mode off");
+                }
+            }
+            "16" => match self.export_text_report() {
+                Ok(msg) => println!("{}", msg),
+                Err(msg) => println!("{}", msg),
+            },
+            "17" => self.print_activity(),
+            "18" => self.review_oldest_tasks(),
+            "19" => self.print_recent_activity(),
+            "20" => self.rerun_search(),
+            "21" => self.review_changes(),
+            "22" => {
+                let Some(name) = self.select_task("Enter task name to mark done: ") else {
+                    return;
+                };
+
+                match self.tasks_manager.mark_done(&name) {
+                    Ok(msg) => println!("{}", msg),
+                    Err(msg) => println!("{}", msg),
+                }
+            }
+            "23" | "q" => self.quit(),
+            "24" => {
+                self.tasks_manager.sort_by_due_date();
+                self.tasks_manager.print_tasks(None);
+            }
+            "25" => match self.sort_tasks() {
+                Ok(_) => self.tasks_manager.print_tasks(None),
+                Err(msg) => println!("{}", msg),
+            },
+            "26" => self.print_tasks_by_priority(),
+            "27" => match self.export_tasks() {
+                Ok(msg) => println!("{}", msg),
+                Err(msg) => println!("{}", msg),
+            },
+            "28" => {
+                let filename = match Self::input("Enter CSV file name to import: ") {
+                    Ok(filename) => filename,
+                    Err(err) => {
+                        println!("Error geting user input {}", err);
+                        return;
+                    }
+                };
+
+                match self.tasks_manager.import_csv(&filename) {
+                    Ok(msg) => println!("{}", msg),
+                    Err(msg) => println!("{}", msg),
+                }
+            }
+            "29" => match self.tasks_manager.undo() {
+                Ok(msg) => println!("{}", msg),
+                Err(msg) => println!("{}", msg),
+            },
+            "30" => self.print_tasks_by_tag(),
+            "31" => self.print_tags(),
+            "32" => match self.test_webhook() {
+                Ok(msg) => println!("{}", msg),
+                Err(msg) => println!("{}", msg),
+            },
+            _ => println!("I don't understand this command"),
+        }
+    }
+}
+
+/// Loads the data file for the non-interactive modes, starting empty when it doesn't exist.
+fn load_or_exit(
+    filename: &str,
+    backup_policy: BackupPolicy,
+    webhook: Option<webhook::Webhook>,
+    hooks: hooks::Hooks,
+) -> TasksManager {
+    let mut tasks_manager = TasksManager::new();
+    tasks_manager.backup_policy = backup_policy;
+    tasks_manager.webhook = webhook;
+    tasks_manager.hooks = hooks;
+    if Path::new(filename).exists() {
+        if let Err(msg) = tasks_manager.read_from_file(filename) {
+            eprintln!("{}", msg);
+            process::exit(1);
+        }
+    }
+    tasks_manager
+}
+
+fn main() {
+    let args = match Args::parse(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(msg) => {
+            eprintln!("{}\n{}", msg, Args::usage());
+            process::exit(2);
+        }
+    };
+
+    let data_file = DataFile::resolve(args.file);
+    let webhook = webhook::Webhook::resolve(args.webhook);
+    // --no-hooks drops the hooks from flags and the environment alike.
+    let hooks = if args.no_hooks {
+        hooks::Hooks::default()
+    } else {
+        args.hooks.with_env()
+    };
+    if let Err(msg) = data_file.create_dir() {
+        eprintln!("{}", msg);
+    }
+    match args.command {
+        Command::Interactive => {}
+        Command::Serve { port } => {
+            let tasks_manager = load_or_exit(&data_file.path, args.backup_policy, webhook, hooks);
+            if let Err(msg) = server::serve(tasks_manager, &data_file.path, port, args.read_only) {
+                eprintln!("{}", msg);
+                process::exit(1);
+            }
+            return;
+        }
+        Command::Run(action) => {
+            let mut tasks_manager =
+                load_or_exit(&data_file.path, args.backup_policy, webhook, hooks);
+            if let Err(msg) = cli::run(&mut tasks_manager, &data_file.path, args.read_only, action)
+            {
+                eprintln!("{}", msg);
+                process::exit(1);
+            }
+            return;
+        }
+        Command::Rpc => {
+            let mut tasks_manager =
+                load_or_exit(&data_file.path, args.backup_policy, webhook, hooks);
+            let stdin = std::io::stdin();
+            if let Err(err) = rpc::run(
+                &mut tasks_manager,
+                &data_file.path,
+                args.read_only,
+                stdin.lock(),
+                std::io::stdout(),
+            ) {
+                eprintln!("Error in rpc session {}", err);
+                process::exit(1);
+            }
+            return;
+        }
+    }
+
+    if let Err(msg) = pomodoro::install_interrupt_handler() {
+        eprintln!("{}", msg);
+    }
+
+    let mut manager = ConsoleManager::new(
+        data_file,
+        args.backup_policy,
+        args.read_only,
+        args.recent_limit,
+        args.autosave,
+        webhook,
+        hooks,
+    );
+    manager.print_menu();
+
+    while manager.running {
+        let changes_before = manager.tasks_manager.unsaved_changes();
+        manager.process_command();
+        manager.autosave(changes_before);
+        println!();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(name: &str, description: &str) -> Task {
+        Task::new(name.to_owned(), description.to_owned(), Priority::Low)
+    }
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2024, 1, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    /// A fresh directory for one test's files.
+    fn temp_dir(test: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("todocli-{}-{}", process::id(), test));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_file(path: &Path, tasks: &[Task]) {
+        TasksManager::write_tasks(File::create(path).unwrap(), tasks).unwrap();
+    }
+
+    fn names(tasks: &[Task]) -> Vec<&str> {
+        tasks.iter().map(|task| task.name.as_str()).collect()
+    }
+
+    fn manager_with(tasks: Vec<Task>) -> TasksManager {
+        let mut manager = TasksManager::new();
+        for task in tasks {
+            manager.add_task(task).unwrap();
+        }
+        manager
+    }
+
+    fn added_at(name: &str, priority: Priority, add_time: DateTime<Local>) -> Task {
+        let mut task = Task::new(name.to_owned(), String::new(), priority);
+        task.add_time = add_time;
+        task
+    }
+
+    #[test]
+    fn priorities_rank_high_over_medium_over_low() {
+        assert!(Priority::High > Priority::Medium);
+        assert!(Priority::Medium > Priority::Low);
+        let mut priorities = vec![Priority::Medium, Priority::High, Priority::Low];
+        priorities.sort();
+        assert!(priorities == [Priority::Low, Priority::Medium, Priority::High]);
+    }
+
+    #[test]
+    fn sorts_by_each_key_in_both_directions() {
+        let mut manager = manager_with(vec![
+            added_at("bread", Priority::High, at(2, 9, 0)),
+            added_at("Apples", Priority::Low, at(3, 9, 0)),
+            added_at("milk", Priority::Medium, at(1, 9, 0)),
+        ]);
+
+        manager.sort_tasks(SortKey::Name, false);
+        assert_eq!(names(&manager.tasks), ["Apples", "bread", "milk"]);
+        manager.sort_tasks(SortKey::Name, true);
+        assert_eq!(names(&manager.tasks), ["milk", "bread", "Apples"]);
+
+        manager.sort_tasks(SortKey::Priority, false);
+        assert_eq!(names(&manager.tasks), ["Apples", "milk", "bread"]);
+        manager.sort_tasks(SortKey::Priority, true);
+        assert_eq!(names(&manager.tasks), ["bread", "milk", "Apples"]);
+
+        manager.sort_tasks(SortKey::AddTime, false);
+        assert_eq!(names(&manager.tasks), ["milk", "bread", "Apples"]);
+        manager.sort_tasks(SortKey::AddTime, true);
+        assert_eq!(names(&manager.tasks), ["Apples", "bread", "milk"]);
+        assert_eq!(manager.unsaved_changes(), 9);
+    }
+
+    #[test]
+    fn sort_ties_keep_the_oldest_first_in_both_directions() {
+        let mut manager = manager_with(vec![
+            added_at("newer high", Priority::High, at(3, 9, 0)),
+            added_at("low", Priority::Low, at(2, 9, 0)),
+            added_at("older high", Priority::High, at(1, 9, 0)),
+        ]);
+
+        manager.sort_tasks(SortKey::Priority, true);
+        assert_eq!(names(&manager.tasks), ["older high", "newer high", "low"]);
+        manager.sort_tasks(SortKey::Priority, false);
+        assert_eq!(names(&manager.tasks), ["low", "older high", "newer high"]);
+    }
+
+    #[test]
+    fn overwrite_replaces_the_file() {
+        let dir = temp_dir("overwrite");
+        let path = dir.join("tasks.json");
+        write_file(&path, &[task("old", "")]);
+
+        let mut manager = TasksManager::new();
+        manager.add_task(task("new", "")).unwrap();
+        let filename = path.to_str().unwrap();
+        manager
+            .store_to_file(filename, SaveMode::Overwrite)
+            .unwrap();
+
+        assert_eq!(names(&TasksManager::read_tasks(&path).unwrap()), ["new"]);
+        assert_eq!(backup::list_backups(filename).unwrap().len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn fail_if_exists_leaves_an_existing_file_alone() {
+        let dir = temp_dir("fail-if-exists");
+        let path = dir.join("tasks.json");
+        write_file(&path, &[task("old", "")]);
+        let before = std::fs::read(&path).unwrap();
+
+        let mut manager = TasksManager::new();
+        manager.add_task(task("new", "")).unwrap();
+        let result = manager.store_to_file(path.to_str().unwrap(), SaveMode::FailIfExists);
+
+        assert!(result.is_err());
+        assert_eq!(std::fs::read(&path).unwrap(), before);
+        assert!(manager.is_dirty());
+
+        let fresh = dir.join("fresh.json");
+        manager
+            .store_to_file(fresh.to_str().unwrap(), SaveMode::FailIfExists)
+            .unwrap();
+        assert_eq!(names(&TasksManager::read_tasks(&fresh).unwrap()), ["new"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn merge_keeps_newer_edits_and_appends_new_names() {
+        let dir = temp_dir("merge");
+        let path = dir.join("tasks.json");
+
+        let mut shared_on_disk = task("shared", "on disk");
+        shared_on_disk.modified_time = Some(at(1, 9, 0));
+        let mut kept_on_disk = task("kept", "on disk");
+        kept_on_disk.modified_time = Some(at(3, 9, 0));
+        write_file(
+            &path,
+            &[shared_on_disk, task("only on disk", ""), kept_on_disk],
+        );
+
+        let mut manager = TasksManager::new();
+        let mut shared = task("shared", "in memory");
+        shared.modified_time = Some(at(2, 9, 0));
+        let mut kept = task("kept", "in memory");
+        kept.modified_time = Some(at(2, 9, 0));
+        for task in [shared, kept, task("only in memory", "")] {
+            manager.add_task(task).unwrap();
+        }
+
+        let msg = manager
+            .store_to_file(path.to_str().unwrap(), SaveMode::Merge)
+            .unwrap();
+        assert_eq!(msg, "Data merged successfully (1 added, 1 updated)");
+
+        let saved = TasksManager::read_tasks(&path).unwrap();
+        assert_eq!(
+            names(&saved),
+            ["shared", "only on disk", "kept", "only in memory"]
+        );
+        assert_eq!(saved[0].description, "in memory");
+        assert_eq!(saved[2].description, "on disk");
+        assert_eq!(names(&manager.tasks), names(&saved));
+        assert!(!manager.is_dirty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn failed_merge_keeps_the_in_memory_tasks() {
+        let dir = temp_dir("failed-merge");
+        let path = dir.join("tasks.json");
+        std::fs::write(&path, "{broken").unwrap();
+
+        let mut manager = TasksManager::new();
+        manager.add_task(task("mine", "")).unwrap();
+        let result = manager.store_to_file(path.to_str().unwrap(), SaveMode::Merge);
+
+        assert!(result.is_err());
+        assert_eq!(names(&manager.tasks), ["mine"]);
+        assert!(manager.is_dirty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn oldest_tasks_are_pending_and_oldest_first() {
+        let mut done = added_at("done long ago", Priority::Low, at(1, 8, 0));
+        done.status = Status::Done;
+        let manager = manager_with(vec![
+            added_at("third", Priority::Low, at(3, 9, 0)),
+            done,
+            added_at("first", Priority::High, at(1, 9, 0)),
+            added_at("second", Priority::Low, at(2, 9, 0)),
+        ]);
+
+        let oldest: Vec<&str> = manager
+            .oldest_tasks(OLDEST_TASKS_LIMIT)
+            .into_iter()
+            .map(|index| manager.tasks[index].name.as_str())
+            .collect();
+        assert_eq!(oldest, ["first", "second", "third"]);
+    }
+
+    #[test]
+    fn oldest_tasks_stop_at_the_limit() {
+        let tasks = (1..=12)
+            .rev()
+            .map(|day| added_at(&format!("day {}", day), Priority::Low, at(day, 9, 0)))
+            .collect();
+        let manager = manager_with(tasks);
+
+        let oldest = manager.oldest_tasks(OLDEST_TASKS_LIMIT);
+        assert_eq!(OLDEST_TASKS_LIMIT, 10);
+        assert_eq!(oldest.len(), 10);
+        assert_eq!(manager.tasks[oldest[0]].name, "day 1");
+        assert_eq!(manager.tasks[oldest[9]].name, "day 10");
+    }
+
+    #[test]
+    fn counts_unsaved_changes_until_saved_or_loaded() {
+        let dir = temp_dir("unsaved-changes");
+        let filename = dir.join("tasks.json").to_str().unwrap().to_owned();
+        let mut manager = TasksManager::new();
+        assert_eq!(manager.active_file(), None);
+        assert_eq!(manager.last_saved(), None);
+        assert!(!manager.is_dirty());
+
+        manager.add_task(task("milk", "")).unwrap();
+        manager.add_task(task("bread", "")).unwrap();
+        manager.mark_done("milk").unwrap();
+        assert_eq!(manager.unsaved_changes(), 3);
+        assert!(manager.is_dirty());
+        assert!(manager.remove_task("eggs").is_err());
+        assert_eq!(manager.unsaved_changes(), 3);
+
+        let before_save = Local::now();
+        manager
+            .store_to_file(&filename, SaveMode::FailIfExists)
+            .unwrap();
+        assert_eq!(manager.unsaved_changes(), 0);
+        assert!(!manager.is_dirty());
+        assert_eq!(manager.active_file(), Some(filename.as_str()));
+        assert!(manager
+            .last_saved()
+            .is_some_and(|saved| saved >= before_save));
+
+        manager.remove_task("bread").unwrap();
+        assert_eq!(manager.unsaved_changes(), 1);
+        manager.read_from_file(&filename).unwrap();
+        assert_eq!(manager.unsaved_changes(), 0);
+        assert_eq!(names(&manager.tasks), ["milk", "bread"]);
+        assert_eq!(manager.active_file(), Some(filename.as_str()));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn blank_searches_match_nothing() {
+        let manager = manager_with(vec![task("Groceries", "milk")]);
+        assert!(manager.search_tasks("").is_empty());
+        assert!(manager.search_tasks("  ").is_empty());
+        assert_eq!(manager.search_tasks("MILK"), [0]);
+    }
+
+    #[test]
+    fn loading_another_file_backs_up_unsaved_work_under_its_own_file() {
+        let dir = temp_dir("load-other");
+        let first = dir.join("first.json").to_str().unwrap().to_owned();
+        let other = dir.join("other.json");
+        write_file(&other, &[task("theirs", "")]);
+
+        let mut manager = manager_with(vec![task("saved", "")]);
+        manager
+            .store_to_file(&first, SaveMode::FailIfExists)
+            .unwrap();
+        manager.add_task(task("unsaved", "")).unwrap();
+        manager.read_from_file(other.to_str().unwrap()).unwrap();
+
+        assert_eq!(names(&manager.tasks), ["theirs"]);
+        assert!(backup::list_backups(other.to_str().unwrap())
+            .unwrap()
+            .is_empty());
+        let backups = backup::list_backups(&first).unwrap();
+        assert_eq!(backups.len(), 1);
+        let backed_up = TasksManager::read_tasks(&backups[0].path).unwrap();
+        assert_eq!(names(&backed_up), ["saved", "unsaved"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn ids(tasks: &[Task]) -> Vec<u64> {
+        tasks.iter().map(|task| task.id).collect()
+    }
+
+    #[test]
+    fn ids_survive_a_save_and_reload() {
+        let dir = temp_dir("ids-reload");
+        let filename = dir.join("tasks.json").to_str().unwrap().to_owned();
+        let mut manager = manager_with(vec![task("a", ""), task("b", ""), task("c", "")]);
+        manager.remove_task("a").unwrap();
+        manager
+            .store_to_file(&filename, SaveMode::FailIfExists)
+            .unwrap();
+
+        let mut reloaded = TasksManager::new();
+        reloaded.read_from_file(&filename).unwrap();
+        assert_eq!(ids(&reloaded.tasks), [2, 3]);
+        assert_eq!(reloaded.next_id, 4);
+        reloaded.add_task(task("d", "")).unwrap();
+        assert_eq!(ids(&reloaded.tasks), [2, 3, 4]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn load_recomputes_the_next_id() {
+        let dir = temp_dir("next-id");
+        let path = dir.join("tasks.json");
+        let mut high = task("high", "");
+        high.id = 41;
+        write_file(&path, &[high]);
+
+        let mut manager = manager_with(vec![task("a", ""), task("b", "")]);
+        assert_eq!(manager.next_id, 3);
+        manager.read_from_file(path.to_str().unwrap()).unwrap();
+        assert_eq!(manager.next_id, 42);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn repairs_missing_and_duplicate_ids() {
+        let mut tasks: Vec<Task> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|name| task(name, ""))
+            .collect();
+        tasks[0].id = 5;
+        tasks[1].id = 0;
+        tasks[2].id = 5;
+        tasks[3].id = 2;
+
+        assert_eq!(number_tasks(&mut tasks), 8);
+        assert_eq!(ids(&tasks), [5, 6, 7, 2]);
+        assert_eq!(number_tasks(&mut []), 1);
+    }
+
+    #[test]
+    fn rejects_duplicate_names() {
+        let mut manager = manager_with(vec![task("milk", ""), task("bread", "")]);
+        assert_eq!(
+            manager.add_task(task("milk", "")).unwrap_err(),
+            "Task with name \"milk\" already exists"
+        );
+
+        let rename = |name: &str| TaskPatch {
+            name: Some(name.to_owned()),
+            ..TaskPatch::default()
+        };
+        assert_eq!(
+            manager.update_task("bread", rename("milk")).unwrap_err(),
+            "Task with name \"milk\" already exists"
+        );
+        assert_eq!(names(&manager.tasks), ["milk", "bread"]);
+        assert!(manager.update_task("bread", rename("bread")).is_ok());
+        assert_eq!(manager.unsaved_changes(), 3);
+    }
+
+    #[test]
+    fn lookup_prefers_hash_id_then_name_then_bare_id() {
+        // Task #1 is named "2", which a bare "2" must find before task #2.
+        let manager = manager_with(vec![task("2", ""), task("x", "")]);
+
+        assert_eq!(manager.lookup("#1"), Some(0));
+        assert_eq!(manager.lookup("#2"), Some(1));
+        assert_eq!(manager.lookup("2"), Some(0));
+        assert_eq!(manager.lookup("1"), Some(0));
+        assert_eq!(manager.lookup("x"), Some(1));
+        assert_eq!(manager.lookup("#3"), None);
+        assert_eq!(manager.lookup("#x"), None);
+    }
+
+    fn tagged(name: &str, tags: &[&str]) -> Task {
+        let mut task = task(name, "");
+        task.tags = normalize_tags(tags.iter().copied());
+        task
+    }
+
+    #[test]
+    fn normalizes_tags() {
+        assert_eq!(
+            normalize_tags([" Work", "home ", "WORK", "", "  "]),
+            ["work", "home"]
+        );
+        assert_eq!(parse_tags("Home, errands,,home"), ["home", "errands"]);
+        assert!(parse_tags("").is_empty());
+    }
+
+    #[test]
+    fn filters_and_counts_tags_ignoring_case() {
+        let manager = manager_with(vec![
+            tagged("milk", &["Shop", "home"]),
+            tagged("report", &["work"]),
+            tagged("bread", &["shop"]),
+        ]);
+
+        let shop: Vec<&str> = manager
+            .filter_by_tag(" SHOP ")
+            .iter()
+            .map(|task| task.name.as_str())
+            .collect();
+        assert_eq!(shop, ["milk", "bread"]);
+        assert!(manager.filter_by_tag("garden").is_empty());
+        assert_eq!(
+            manager.list_tags(),
+            [
+                ("home".to_owned(), 1),
+                ("shop".to_owned(), 2),
+                ("work".to_owned(), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn edits_tags_with_plus_minus_syntax() {
+        let current = vec!["home".to_owned(), "shop".to_owned()];
+        let edit = |input: &str| ConsoleManager::edit_tags(&current, input);
+
+        assert_eq!(edit(""), Ok(None));
+        assert_eq!(edit(" , "), Ok(None));
+        assert_eq!(edit("-"), Ok(Some(vec![])));
+        assert_eq!(
+            edit("Work, play"),
+            Ok(Some(vec!["work".to_owned(), "play".to_owned()]))
+        );
+        assert_eq!(
+            edit("+Urgent, -home, +shop"),
+            Ok(Some(vec!["shop".to_owned(), "urgent".to_owned()]))
+        );
+        assert_eq!(edit("-garden"), Ok(Some(current.clone())));
+        assert!(edit("+a, b").is_err());
+        assert!(edit("a, -b").is_err());
+    }
+
+    #[test]
+    fn tags_survive_a_save_and_reload() {
+        let dir = temp_dir("tags-reload");
+        let filename = dir.join("tasks.json").to_str().unwrap().to_owned();
+        let mut manager = manager_with(vec![tagged("milk", &["Shop", "home"]), task("bread", "")]);
+        manager
+            .store_to_file(&filename, SaveMode::FailIfExists)
+            .unwrap();
+
+        let mut reloaded = TasksManager::new();
+        reloaded.read_from_file(&filename).unwrap();
+        assert_eq!(reloaded.tasks[0].tags, ["shop", "home"]);
+        assert!(reloaded.tasks[1].tags.is_empty());
+        assert_eq!(reloaded.filter_by_tag("Home").len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn json(task: &Task) -> serde_json::Value {
+        serde_json::to_value(task).unwrap()
+    }
+
+    #[test]
+    fn undo_puts_a_removed_task_back_where_it_was() {
+        let mut manager = manager_with(vec![
+            added_at("first", Priority::Low, at(1, 9, 0)),
+            added_at("second", Priority::High, at(2, 9, 0)),
+            added_at("third", Priority::Low, at(3, 9, 0)),
+        ]);
+        let removed = json(&manager.tasks[1]);
+
+        manager.remove_task("second").unwrap();
+        assert_eq!(manager.undo().unwrap(), "Restored removed task \"second\"");
+
+        assert_eq!(names(&manager.tasks), ["first", "second", "third"]);
+        assert_eq!(manager.tasks[1].add_time, at(2, 9, 0));
+        assert_eq!(json(&manager.tasks[1]), removed);
+        assert_eq!(manager.lookup("second"), Some(1));
+    }
+
+    #[test]
+    fn undo_restores_every_edited_field() {
+        let mut original = added_at("milk", Priority::Low, at(1, 9, 0));
+        original.description = "2%".to_owned();
+        original.due_date = Some(at(5, 18, 0));
+        original.tags = vec!["shop".to_owned()];
+        let mut manager = manager_with(vec![original]);
+        let before = json(&manager.tasks[0]);
+
+        manager
+            .update_task(
+                "milk",
+                TaskPatch {
+                    name: Some("oat milk".to_owned()),
+                    description: Some("barista".to_owned()),
+                    priority: Some(Priority::High),
+                    due_date: Some(None),
+                    tags: Some(vec!["cafe".to_owned()]),
+                },
+            )
+            .unwrap();
+        manager.undo().unwrap();
+        assert_eq!(json(&manager.tasks[0]), before);
+
+        manager.mark_done("milk").unwrap();
+        manager.undo().unwrap();
+        assert_eq!(json(&manager.tasks[0]), before);
+    }
+
+    #[test]
+    fn repeated_undo_walks_back_through_the_history() {
+        let mut manager = manager_with(vec![
+            added_at("b", Priority::Low, at(2, 9, 0)),
+            added_at("a", Priority::High, at(1, 9, 0)),
+        ]);
+        manager.sort_tasks(SortKey::Name, false);
+        manager.remove_task("a").unwrap();
+
+        manager.undo().unwrap();
+        assert_eq!(names(&manager.tasks), ["a", "b"]);
+        manager.undo().unwrap();
+        assert_eq!(names(&manager.tasks), ["b", "a"]);
+        manager.undo().unwrap();
+        assert_eq!(names(&manager.tasks), ["b"]);
+        manager.undo().unwrap();
+        assert!(manager.tasks.is_empty());
+        assert_eq!(manager.undo().unwrap_err(), "Nothing to undo");
+    }
+
+    #[test]
+    fn undo_history_is_capped() {
+        let mut manager = TasksManager::new();
+        for number in 0..MAX_UNDO + 5 {
+            manager.add_task(task(&number.to_string(), "")).unwrap();
+        }
+        for _ in 0..MAX_UNDO {
+            manager.undo().unwrap();
+        }
+        assert_eq!(manager.tasks.len(), 5);
+        assert_eq!(manager.undo().unwrap_err(), "Nothing to undo");
+    }
+
+    #[test]
+    fn undo_explains_a_history_cleared_by_a_load() {
+        let dir = temp_dir("undo-after-load");
+        let path = dir.join("tasks.json");
+        write_file(&path, &[task("saved", "")]);
+
+        let mut manager = manager_with(vec![task("unsaved", "")]);
+        manager.read_from_file(path.to_str().unwrap()).unwrap();
+        assert_eq!(
+            manager.undo().unwrap_err(),
+            "Nothing to undo, the history was cleared when the task list was replaced from a file"
+        );
+
+        manager.add_task(task("new", "")).unwrap();
+        manager.undo().unwrap();
+        assert_eq!(manager.undo().unwrap_err(), "Nothing to undo");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn read_only_sessions_leave_the_tasks_untouched() {
+        let dir = temp_dir("read-only");
+        let path = dir.join("tasks.json");
+        let filename = path.to_str().unwrap().to_owned();
+        let mut done = task("bread", "");
+        done.status = Status::Done;
+        write_file(&path, &[task("milk", "oat"), done]);
+        let on_disk = std::fs::read(&path).unwrap();
+
+        let data_file = DataFile {
+            path: filename.clone(),
+            source: PathSource::Flag,
+        };
+        let mut console = ConsoleManager::new(
+            data_file,
+            BackupPolicy::default(),
+            true,
+            DEFAULT_RECENT_LIMIT,
+            true,
+            None,
+            hooks::Hooks::default(),
+        );
+        let serialized =
+            |tasks_manager: &TasksManager| serde_json::to_string(&tasks_manager.tasks).unwrap();
+        let loaded = serialized(&console.tasks_manager);
+
+        // Refused before they read any input, so no stdin is needed.
+        let mutating = [
+            "1", "3", "4", "6", "10", "12", "13", "18", "22", "24", "25", "28", "29",
+        ];
+        for command in mutating {
+            assert!(ConsoleManager::is_mutating(command));
+            let changes_before = console.tasks_manager.unsaved_changes();
+            console.run_command(command);
+            console.autosave(changes_before);
+        }
+        assert_eq!(serialized(&console.tasks_manager), loaded);
+
+        let requests = br#"{"id": 1, "method": "add_task", "params": {"name": "eggs"}}
+{"id": 2, "method": "edit_task", "params": {"name": "milk", "task": {"name": "cream"}}}
+{"id": 3, "method": "remove_task", "params": {"name": "bread"}}
+{"id": 4, "method": "save"}
+"#;
+        let mut responses = vec![];
+        rpc::run(
+            &mut console.tasks_manager,
+            &filename,
+            true,
+            &requests[..],
+            &mut responses,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(responses)
+                .unwrap()
+                .matches("Read-only mode")
+                .count(),
+            4
+        );
+        assert_eq!(serialized(&console.tasks_manager), loaded);
+
+        let actions = [
+            cli::Action::Add(task("eggs", "")),
+            cli::Action::Done("milk".to_owned()),
+            cli::Action::Remove("bread".to_owned()),
+        ];
+        for action in actions {
+            assert!(cli::run(&mut console.tasks_manager, &filename, true, action).is_err());
+        }
+        assert_eq!(serialized(&console.tasks_manager), loaded);
+
+        assert!(!console.tasks_manager.is_dirty());
+        assert_eq!(std::fs::read(&path).unwrap(), on_disk);
+        assert!(backup::list_backups(&filename).unwrap().is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn webhook_failures_only_warn() {
+        let mut manager = TasksManager::new();
+        // Nothing listens on port 1, so every post fails.
+        manager.webhook = Some(webhook::Webhook::new("http://127.0.0.1:1/hook".to_owned()));
+
+        let added = manager.add_task(task("milk", "")).unwrap();
+        assert!(added.contains("warning: Error posting to webhook"));
+        assert!(manager
+            .mark_done("milk")
+            .unwrap()
+            .contains("warning: Error posting to webhook"));
+        assert!(manager
+            .remove_task("milk")
+            .unwrap()
+            .contains("warning: Error posting to webhook"));
+        assert!(manager.tasks.is_empty());
+        assert_eq!(manager.unsaved_changes(), 3);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failed_hooks_do_not_roll_back() {
+        let dir = temp_dir("failed_hooks");
+        let log = dir.join("added.log");
+        let mut manager = TasksManager::new();
+        manager.hooks.set(
+            TaskEvent::Added,
+            format!("echo \"$TODO_NAME\" >> {}; exit 1", log.display()),
+        );
+        manager.hooks.set(TaskEvent::Removed, "exit 2".to_owned());
+
+        let added = manager.add_task(task("milk", "")).unwrap();
+        assert!(added.contains("warning: Hook on_add failed"), "{}", added);
+        assert_eq!(names(&manager.tasks), ["milk"]);
+
+        // Tasks imported from CSV don't fire on_add.
+        let csv = dir.join("tasks.csv");
+        std::fs::write(&csv, "name\nbread\n").unwrap();
+        manager.import_csv(csv.to_str().unwrap()).unwrap();
+        assert_eq!(std::fs::read_to_string(&log).unwrap(), "milk\n");
+
+        let removed = manager.remove_task("milk").unwrap();
+        assert!(
+            removed.contains("warning: Hook on_remove failed"),
+            "{}",
+            removed
+        );
+        assert_eq!(names(&manager.tasks), ["bread"]);
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
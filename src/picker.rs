@@ -0,0 +1,241 @@
+use std::io::{IsTerminal, Write};
+
+use crossterm::{
+    cursor::{MoveToColumn, MoveUp},
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    queue,
+    style::{Attribute, Print, SetAttribute},
+    terminal::{self, Clear, ClearType},
+};
+
+const MAX_SHOWN: usize = 10;
+
+const MATCH_SCORE: i64 = 16;
+const CONSECUTIVE_BONUS: i64 = 24;
+const WORD_START_BONUS: i64 = 20;
+const GAP_PENALTY: i64 = 1;
+
+/// Scores `candidate` against `query` as an fzf-style subsequence match.
+///
+/// Returns `None` unless every query character appears in order, ignoring
+/// case. Consecutive runs and matches at word starts score higher, gaps lower.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let candidate: Vec<char> = candidate.chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+
+    for query_char in query.chars().flat_map(char::to_lowercase) {
+        let found = (position..candidate.len())
+            .find(|&i| candidate[i].to_lowercase().eq(std::iter::once(query_char)))?;
+
+        score += MATCH_SCORE;
+        match previous {
+            Some(previous) if previous + 1 == found => score += CONSECUTIVE_BONUS,
+            Some(previous) => score -= GAP_PENALTY * (found - previous - 1) as i64,
+            None => score -= GAP_PENALTY * found as i64,
+        }
+        if found == 0 || !candidate[found - 1].is_alphanumeric() {
+            score += WORD_START_BONUS;
+        }
+
+        previous = Some(found);
+        position = found + 1;
+    }
+
+    Some(score)
+}
+
+/// Indices of the candidates matching `query`, best first; ties keep list order.
+pub fn rank(query: &str, candidates: &[String]) -> Vec<usize> {
+    let mut matches: Vec<(i64, usize)> = candidates
+        .iter()
+        .enumerate()
+        .filter_map(|(index, candidate)| Some((fuzzy_score(query, candidate)?, index)))
+        .collect();
+    matches.sort_by_key(|&(score, index)| (std::cmp::Reverse(score), index));
+    matches.into_iter().map(|(_, index)| index).collect()
+}
+
+/// What the user entered in the picker.
+pub struct Pick {
+    /// The text typed to filter the candidates.
+    pub query: String,
+    /// Index of the chosen candidate; `None` when nothing matched the query.
+    pub choice: Option<usize>,
+}
+
+/// Lets the user pick one of `candidates` by typing to filter them.
+///
+/// Returns `None` if cancelled with Esc. Without a terminal on both ends it
+/// reads one plain line instead, which chooses no candidate.
+pub fn pick(prompt: &str, candidates: &[String]) -> std::io::Result<Option<Pick>> {
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        print!("{}", prompt);
+        std::io::stdout().flush()?;
+        let mut buffer = String::new();
+        if std::io::stdin().read_line(&mut buffer)? == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        return Ok(Some(Pick {
+            query: buffer.trim().to_owned(),
+            choice: None,
+        }));
+    }
+
+    terminal::enable_raw_mode()?;
+    let result = run(prompt, candidates);
+    terminal::disable_raw_mode()?;
+    println!();
+    result
+}
+
+fn run(prompt: &str, candidates: &[String]) -> std::io::Result<Option<Pick>> {
+    let mut stdout = std::io::stdout();
+    let mut query = String::new();
+    let mut selected = 0;
+
+    loop {
+        let matches = rank(&query, candidates);
+        selected = selected.min(matches.len().min(MAX_SHOWN).saturating_sub(1));
+        render(&mut stdout, prompt, &query, candidates, &matches, selected)?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind == KeyEventKind::Release {
+            continue;
+        }
+
+        match key.code {
+            KeyCode::Esc => {
+                finish(&mut stdout, prompt, "(cancelled)")?;
+                return Ok(None);
+            }
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                finish(&mut stdout, prompt, "(cancelled)")?;
+                return Ok(None);
+            }
+            KeyCode::Enter => {
+                let choice = matches.get(selected).copied();
+                let answer = choice.map_or(query.as_str(), |index| &candidates[index]);
+                finish(&mut stdout, prompt, answer)?;
+                return Ok(Some(Pick { query, choice }));
+            }
+            KeyCode::Up => selected = selected.saturating_sub(1),
+            KeyCode::Down => selected += 1,
+            KeyCode::Backspace => {
+                query.pop();
+                selected = 0;
+            }
+            KeyCode::Char(c) => {
+                query.push(c);
+                selected = 0;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn render(
+    stdout: &mut std::io::Stdout,
+    prompt: &str,
+    query: &str,
+    candidates: &[String],
+    matches: &[usize],
+    selected: usize,
+) -> std::io::Result<()> {
+    queue!(stdout, MoveToColumn(0), Clear(ClearType::FromCursorDown))?;
+
+    let shown = matches.len().min(MAX_SHOWN);
+    for (row, &index) in matches.iter().take(MAX_SHOWN).enumerate() {
+        queue!(stdout, Print("\r\n"))?;
+        if row == selected {
+            queue!(
+                stdout,
+                SetAttribute(Attribute::Reverse),
+                Print(format!("> {}", candidates[index])),
+                SetAttribute(Attribute::Reset)
+            )?;
+        } else {
+            queue!(stdout, Print(format!("  {}", candidates[index])))?;
+        }
+    }
+    if matches.len() > MAX_SHOWN {
+        queue!(
+            stdout,
+            Print(format!("\r\n  … {} more", matches.len() - MAX_SHOWN))
+        )?;
+    }
+
+    let lines = shown + usize::from(matches.len() > MAX_SHOWN);
+    if lines > 0 {
+        queue!(stdout, MoveUp(lines as u16))?;
+    }
+    queue!(stdout, MoveToColumn(0), Print(prompt), Print(query))?;
+    stdout.flush()
+}
+
+/// Clears the candidate list, leaving the prompt with the final answer.
+fn finish(stdout: &mut std::io::Stdout, prompt: &str, answer: &str) -> std::io::Result<()> {
+    queue!(
+        stdout,
+        MoveToColumn(0),
+        Clear(ClearType::FromCursorDown),
+        Print(prompt),
+        Print(answer)
+    )?;
+    stdout.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidates(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn requires_every_query_char_in_order() {
+        assert!(fuzzy_score("bm", "buy milk").is_some());
+        assert_eq!(fuzzy_score("mb", "buy milk"), None);
+        assert_eq!(fuzzy_score("milkk", "buy milk"), None);
+        assert_eq!(fuzzy_score("", "buy milk"), Some(0));
+    }
+
+    #[test]
+    fn ignores_case() {
+        assert_eq!(
+            fuzzy_score("MILK", "buy milk"),
+            fuzzy_score("milk", "buy milk")
+        );
+        assert_eq!(
+            fuzzy_score("milk", "Buy MILK"),
+            fuzzy_score("milk", "buy milk")
+        );
+    }
+
+    #[test]
+    fn rewards_consecutive_runs() {
+        let run = fuzzy_score("abc", "xabcx").unwrap();
+        let spread = fuzzy_score("abc", "xaxbxc").unwrap();
+        assert!(run > spread, "{} <= {}", run, spread);
+    }
+
+    #[test]
+    fn rewards_word_starts() {
+        let word_start = fuzzy_score("m", "buy milk").unwrap();
+        let mid_word = fuzzy_score("m", "hummus").unwrap();
+        assert!(word_start > mid_word, "{} <= {}", word_start, mid_word);
+    }
+
+    #[test]
+    fn ranks_best_first_and_keeps_ties_in_order() {
+        let names = candidates(&["hummus", "milk", "bread", "buy milk", "oat milk"]);
+        assert_eq!(rank("milk", &names), [1, 3, 4]);
+        assert_eq!(rank("mu", &names), [0]);
+        assert_eq!(rank("", &names), [0, 1, 2, 3, 4]);
+        assert!(rank("zz", &names).is_empty());
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    io::Write,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, TryRecvError},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+pub const DEFAULT_WORK_MINUTES: u64 = 25;
+pub const BREAK_MINUTES: u64 = 5;
+/// Longest countdown accepted: a day.
+pub const MAX_MINUTES: u64 = 24 * 60;
+
+const BAR_WIDTH: usize = 30;
+
+/// Set while a countdown runs so Ctrl-C cancels it instead of exiting.
+static RUNNING: AtomicBool = AtomicBool::new(false);
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+pub enum Outcome {
+    Finished,
+    Cancelled,
+}
+
+/// Installs the Ctrl-C handler: cancels a running countdown, otherwise exits.
+pub fn install_interrupt_handler() -> Result<(), String> {
+    ctrlc::set_handler(|| {
+        if RUNNING.load(Ordering::SeqCst) {
+            CANCELLED.store(true, Ordering::SeqCst);
+        } else {
+            std::process::exit(130);
+        }
+    })
+    .map_err(|err| format!("Error installing Ctrl-C handler {}", err))
+}
+
+/// Reads stdin lines on a helper thread so a countdown can watch for "q".
+///
+/// At most one read is outstanding and its line is always consumed by the
+/// next `read_line`, so no input is lost to the console afterwards.
+pub struct LineReader {
+    pending: Option<Receiver<String>>,
+}
+
+impl LineReader {
+    pub fn new() -> Self {
+        Self { pending: None }
+    }
+
+    fn receiver(&mut self) -> &Receiver<String> {
+        self.pending.get_or_insert_with(|| {
+            let (sender, receiver) = mpsc::channel();
+            thread::spawn(move || {
+                let mut buffer = String::new();
+                let _ = std::io::stdin().read_line(&mut buffer);
+                let _ = sender.send(buffer.trim().to_owned());
+            });
+            receiver
+        })
+    }
+
+    fn try_read_line(&mut self) -> Option<String> {
+        match self.receiver().try_recv() {
+            Ok(line) => {
+                self.pending = None;
+                Some(line)
+            }
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                self.pending = None;
+                Some(String::new())
+            }
+        }
+    }
+
+    /// Waits out an outstanding read so the console can use stdin again.
+    pub fn finish(&mut self) {
+        if self.pending.is_some() {
+            print!("Press Enter to continue");
+            let _ = std::io::stdout().flush();
+            self.read_line();
+        }
+    }
+
+    pub fn read_line(&mut self) -> String {
+        let line = self.receiver().recv().unwrap_or_default();
+        self.pending = None;
+        line
+    }
+}
+
+/// Counts down `minutes` on a redrawn progress line until done or cancelled.
+pub fn countdown(label: &str, minutes: u64, reader: &mut LineReader) -> Outcome {
+    let total = Duration::from_secs(minutes.min(MAX_MINUTES) * 60);
+    let start = Instant::now();
+    CANCELLED.store(false, Ordering::SeqCst);
+    RUNNING.store(true, Ordering::SeqCst);
+
+    let outcome = loop {
+        if CANCELLED.load(Ordering::SeqCst) {
+            break Outcome::Cancelled;
+        }
+        if let Some(line) = reader.try_read_line() {
+            if line.eq_ignore_ascii_case("q") {
+                break Outcome::Cancelled;
+            }
+        }
+
+        let elapsed = start.elapsed();
+        if elapsed >= total {
+            break Outcome::Finished;
+        }
+
+        print!("\r{}", progress_line(label, elapsed, total));
+        let _ = std::io::stdout().flush();
+        thread::sleep(Duration::from_millis(200));
+    };
+
+    RUNNING.store(false, Ordering::SeqCst);
+    println!();
+    if let Outcome::Finished = outcome {
+        print!("\x07");
+        let _ = std::io::stdout().flush();
+    }
+    outcome
+}
+
+fn progress_line(label: &str, elapsed: Duration, total: Duration) -> String {
+    let left = (total - elapsed).as_secs();
+    let filled = (elapsed.as_secs_f64() / total.as_secs_f64() * BAR_WIDTH as f64) as usize;
+    format!(
+        "{} [{}{}] {:02}:{:02} left (q + Enter to cancel)",
+        label,
+        "#".repeat(filled),
+        "-".repeat(BAR_WIDTH - filled),
+        left / 60,
+        left % 60
+    )
+}
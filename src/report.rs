@@ -0,0 +1,298 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Days, Local, NaiveDate};
+
+use crate::{Priority, Task};
+
+pub const DEFAULT_WIDTH: usize = 80;
+
+/// Narrowest width that still fits the fixed-format date lines.
+pub const MIN_WIDTH: usize = 40;
+
+const INDENT: &str = "    ";
+
+/// Renders a printable plain-text report, one section per priority.
+pub fn text_report(tasks: &[Task], width: usize, date: DateTime<Local>) -> String {
+    let mut lines = vec![];
+    let title = format!("Tasks as of {}", date.format("%d-%m-%Y"));
+    lines.extend(wrap(&title, width));
+    lines.push("=".repeat(title.chars().count().min(width)));
+
+    let mut number = 1;
+    let mut counts = vec![];
+    for priority in [Priority::High, Priority::Medium, Priority::Low] {
+        let section: Vec<&Task> = tasks
+            .iter()
+            .filter(|task| task.priority == priority)
+            .collect();
+        counts.push(format!("{}: {}", priority, section.len()));
+        if section.is_empty() {
+            continue;
+        }
+
+        lines.push(String::new());
+        let heading = format!("{} priority", priority);
+        let underline = "-".repeat(heading.chars().count());
+        lines.push(heading);
+        lines.push(underline);
+
+        for task in section {
+            lines.push(String::new());
+            let prefix = format!("{}. ", number);
+            for (index, line) in wrap(&task.name, width - prefix.len())
+                .into_iter()
+                .enumerate()
+            {
+                let lead = if index == 0 {
+                    prefix.clone()
+                } else {
+                    " ".repeat(prefix.len())
+                };
+                lines.push(format!("{}{}", lead, line));
+            }
+            for paragraph in task.description.lines() {
+                for line in wrap(paragraph, width - INDENT.len()) {
+                    lines.push(format!("{}{}", INDENT, line).trim_end().to_owned());
+                }
+            }
+            lines.push(format!(
+                "{}Created: {}",
+                INDENT,
+                task.add_time.format("%d-%m-%Y %H:%M")
+            ));
+            if let Some(due_date) = task.due_date {
+                lines.push(format!(
+                    "{}Due: {}",
+                    INDENT,
+                    due_date.format("%d-%m-%Y %H:%M")
+                ));
+            }
+            number += 1;
+        }
+    }
+
+    lines.push(String::new());
+    lines.extend(wrap(
+        &format!("Total: {} tasks ({})", tasks.len(), counts.join(", ")),
+        width,
+    ));
+
+    let mut report = lines.join("\n");
+    report.push('\n');
+    report
+}
+
+/// Greedy word wrap; words longer than `width`, such as URLs, are split.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    let mut line_len = 0;
+
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+
+        if line_len > 0 && line_len + 1 + word.len() > width {
+            lines.push(std::mem::take(&mut line));
+            line_len = 0;
+        }
+
+        // Only reachable on an empty line, where the word has the full width.
+        while word.len() > width {
+            let rest = word.split_off(width);
+            lines.push(word.into_iter().collect());
+            word = rest;
+        }
+
+        if line_len > 0 {
+            line.push(' ');
+            line_len += 1;
+        }
+        line_len += word.len();
+        line.extend(word);
+    }
+
+    if line_len > 0 || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+pub const DEFAULT_ACTIVITY_DAYS: u32 = 14;
+
+const BAR_WIDTH: usize = 40;
+
+/// Counts tasks created on each of the `days` days ending with `today`, zeros included.
+pub fn created_per_day(tasks: &[Task], today: NaiveDate, days: u32) -> BTreeMap<NaiveDate, u32> {
+    let mut buckets: BTreeMap<NaiveDate, u32> = (0..days)
+        .filter_map(|offset| today.checked_sub_days(Days::new(offset as u64)))
+        .map(|date| (date, 0))
+        .collect();
+
+    for task in tasks {
+        if let Some(count) = buckets.get_mut(&task.add_time.date_naive()) {
+            *count += 1;
+        }
+    }
+    buckets
+}
+
+/// Renders labeled horizontal bars scaled so the largest value fills the bar width.
+pub fn bar_chart(rows: &[(String, u32)]) -> String {
+    let label_width = rows
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let max = rows.iter().map(|(_, value)| *value).max().unwrap_or(0);
+
+    let mut chart = String::new();
+    for (label, value) in rows {
+        let bar = match *value {
+            0 => String::new(),
+            value => "#".repeat((value as usize * BAR_WIDTH).div_ceil(max as usize)) + " ",
+        };
+        chart.push_str(&format!(
+            "{:<width$} | {}{}\n",
+            label,
+            bar,
+            value,
+            width = label_width
+        ));
+    }
+    chart
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn created_at(day: u32, hour: u32, minute: u32) -> Task {
+        let mut task = Task::new("task".to_owned(), String::new(), Priority::Low);
+        task.add_time = Local
+            .with_ymd_and_hms(2024, 3, day, hour, minute, 0)
+            .unwrap();
+        task
+    }
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2024, 3, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn wraps_words_within_the_width() {
+        assert_eq!(
+            wrap("the quick brown fox jumps", 10),
+            ["the quick", "brown fox", "jumps"]
+        );
+        assert_eq!(wrap("", 10), [""]);
+        assert_eq!(wrap("exactly10!", 10), ["exactly10!"]);
+    }
+
+    #[test]
+    fn splits_words_longer_than_the_width() {
+        let url = "https://example.com/a/very/long/path";
+        let lines = wrap(&format!("see {} now", url), 12);
+        assert_eq!(
+            lines,
+            ["see", "https://exam", "ple.com/a/ve", "ry/long/path", "now"]
+        );
+        assert_eq!(lines[1..4].concat(), url);
+    }
+
+    #[test]
+    fn no_report_line_exceeds_the_width() {
+        let mut task = Task::new(
+            "Read https://example.com/".to_owned() + &"segment/".repeat(20),
+            format!("{}\n{}", "word ".repeat(40), "x".repeat(200)),
+            Priority::Medium,
+        );
+        task.due_date = Some(at(8, 18, 0));
+        let tasks = [task];
+
+        for width in [MIN_WIDTH, 57, DEFAULT_WIDTH] {
+            let report = text_report(&tasks, width, at(10, 12, 0));
+            for line in report.lines() {
+                assert!(
+                    line.chars().count() <= width,
+                    "{} columns at width {}: {:?}",
+                    line.chars().count(),
+                    width,
+                    line
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn lays_out_sections_and_summary() {
+        let mut read = Task::new(
+            "Read https://example.com/a/very/long/path/that/does/not/fit/anywhere".to_owned(),
+            "First paragraph with several words to wrap around.\nSecond".to_owned(),
+            Priority::High,
+        );
+        read.add_time = at(1, 9, 30);
+        read.due_date = Some(at(8, 18, 0));
+        let mut water = Task::new("Water plants".to_owned(), String::new(), Priority::Low);
+        water.add_time = at(2, 10, 0);
+
+        let report = text_report(&[water, read], MIN_WIDTH, at(10, 12, 0));
+        assert_eq!(
+            report,
+            "Tasks as of 10-03-2024
+======================
+
+High priority
+-------------
+
+1. Read
+   https://example.com/a/very/long/path/
+   that/does/not/fit/anywhere
+    First paragraph with several words
+    to wrap around.
+    Second
+    Created: 01-03-2024 09:30
+    Due: 08-03-2024 18:00
+
+Low priority
+------------
+
+2. Water plants
+    Created: 02-03-2024 10:00
+
+Total: 2 tasks (High: 1, Medium: 0, Low:
+1)
+"
+        );
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, day).unwrap()
+    }
+
+    #[test]
+    fn buckets_tasks_by_local_day_around_midnight() {
+        let tasks = [
+            created_at(7, 23, 59),
+            created_at(8, 0, 0),
+            created_at(9, 23, 59),
+            created_at(10, 0, 0),
+            created_at(10, 23, 59),
+            created_at(11, 0, 0),
+        ];
+
+        let counts = created_per_day(&tasks, date(10), 3);
+        let counts: Vec<(NaiveDate, u32)> = counts.into_iter().collect();
+        assert_eq!(counts, [(date(8), 1), (date(9), 1), (date(10), 2)]);
+    }
+
+    #[test]
+    fn keeps_days_without_tasks() {
+        let counts = created_per_day(&[], date(10), 2);
+        let counts: Vec<(NaiveDate, u32)> = counts.into_iter().collect();
+        assert_eq!(counts, [(date(9), 0), (date(10), 0)]);
+    }
+}
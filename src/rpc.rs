@@ -0,0 +1,314 @@
+use std::io::{BufRead, Write};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{SaveMode, Task, TaskPatchPayload, TaskPayload, TasksManager};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const TASK_ERROR: i64 = 1;
+const READ_ONLY: i64 = 2;
+
+const MUTATING_METHODS: [&str; 4] = ["add_task", "edit_task", "remove_task", "save"];
+
+#[derive(Deserialize)]
+struct Request {
+    method: String,
+    #[serde(default)]
+    params: Value,
+    #[serde(default)]
+    id: Value,
+}
+
+#[derive(Deserialize)]
+struct NameParams {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct SearchParams {
+    query: String,
+}
+
+#[derive(Deserialize)]
+struct EditParams {
+    name: String,
+    task: TaskPatchPayload,
+}
+
+#[derive(Deserialize, Default)]
+struct FileParams {
+    filename: Option<String>,
+}
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Answers one JSON request per input line with one JSON response line until EOF.
+pub fn run(
+    tasks_manager: &mut TasksManager,
+    filename: &str,
+    read_only: bool,
+    mut input: impl BufRead,
+    mut output: impl Write,
+) -> std::io::Result<()> {
+    let mut buffer = vec![];
+    loop {
+        buffer.clear();
+        if input.read_until(b'\n', &mut buffer)? == 0 {
+            break;
+        }
+
+        // A line that isn't UTF-8 gets an error response rather than ending the session.
+        let response = match std::str::from_utf8(&buffer) {
+            Ok(line) if line.trim().is_empty() => continue,
+            Ok(line) => handle_line(tasks_manager, filename, read_only, line.trim_end()),
+            Err(err) => error_response(Value::Null, RpcError::new(PARSE_ERROR, err.to_string())),
+        };
+        writeln!(output, "{}", response)?;
+        output.flush()?;
+    }
+
+    output.flush()
+}
+
+fn handle_line(
+    tasks_manager: &mut TasksManager,
+    filename: &str,
+    read_only: bool,
+    line: &str,
+) -> Value {
+    let value: Value = match serde_json::from_str(line) {
+        Ok(value) => value,
+        Err(err) => {
+            return error_response(Value::Null, RpcError::new(PARSE_ERROR, err.to_string()))
+        }
+    };
+
+    let request: Request = match serde_json::from_value(value) {
+        Ok(request) => request,
+        Err(err) => {
+            return error_response(Value::Null, RpcError::new(INVALID_REQUEST, err.to_string()))
+        }
+    };
+
+    if read_only && MUTATING_METHODS.contains(&request.method.as_str()) {
+        return error_response(
+            request.id,
+            RpcError::new(
+                READ_ONLY,
+                format!("Read-only mode: \"{}\" is disabled", request.method),
+            ),
+        );
+    }
+
+    match dispatch(tasks_manager, filename, &request) {
+        Ok(result) => json!({ "id": request.id, "result": result }),
+        Err(err) => error_response(request.id, err),
+    }
+}
+
+fn dispatch(
+    tasks_manager: &mut TasksManager,
+    filename: &str,
+    request: &Request,
+) -> Result<Value, RpcError> {
+    match request.method.as_str() {
+        "add_task" => {
+            let payload: TaskPayload = params(&request.params)?;
+            let task = payload
+                .into_task()
+                .map_err(|msg| RpcError::new(INVALID_PARAMS, msg))?;
+            tasks_manager
+                .add_task(task)
+                .map_err(|msg| RpcError::new(TASK_ERROR, msg))?;
+            to_value(&tasks_manager.tasks.last())
+        }
+        "list_tasks" => to_value(&tasks_manager.tasks),
+        "find_task" => {
+            let NameParams { name } = params(&request.params)?;
+            match tasks_manager.lookup(&name) {
+                Some(index) => to_value(&tasks_manager.tasks[index]),
+                None => Err(RpcError::new(
+                    TASK_ERROR,
+                    format!("Task with name \"{}\" doesn't exist", name),
+                )),
+            }
+        }
+        "search_tasks" => {
+            let SearchParams { query } = params(&request.params)?;
+            let tasks: Vec<&Task> = tasks_manager
+                .search_tasks(&query)
+                .into_iter()
+                .map(|index| &tasks_manager.tasks[index])
+                .collect();
+            to_value(&tasks)
+        }
+        "edit_task" => {
+            let EditParams { name, task } = params(&request.params)?;
+            let patch = task
+                .into_patch()
+                .map_err(|msg| RpcError::new(INVALID_PARAMS, msg))?;
+            message(tasks_manager.update_task(&name, patch))
+        }
+        "remove_task" => {
+            let NameParams { name } = params(&request.params)?;
+            message(tasks_manager.remove_task(&name))
+        }
+        "save" => {
+            let FileParams { filename: target } = optional_params(&request.params)?;
+            message(
+                tasks_manager
+                    .store_to_file(target.as_deref().unwrap_or(filename), SaveMode::Overwrite),
+            )
+        }
+        "load" => {
+            let FileParams { filename: target } = optional_params(&request.params)?;
+            message(tasks_manager.read_from_file(target.as_deref().unwrap_or(filename)))
+        }
+        method => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Unknown method \"{}\"", method),
+        )),
+    }
+}
+
+fn params<T: for<'de> Deserialize<'de>>(params: &Value) -> Result<T, RpcError> {
+    serde_json::from_value(params.clone())
+        .map_err(|err| RpcError::new(INVALID_PARAMS, err.to_string()))
+}
+
+fn optional_params<T: for<'de> Deserialize<'de> + Default>(params: &Value) -> Result<T, RpcError> {
+    if params.is_null() {
+        Ok(T::default())
+    } else {
+        self::params(params)
+    }
+}
+
+fn message(result: Result<String, String>) -> Result<Value, RpcError> {
+    result
+        .map(|msg| json!({ "message": msg }))
+        .map_err(|msg| RpcError::new(TASK_ERROR, msg))
+}
+
+fn to_value<T: serde::Serialize + ?Sized>(value: &T) -> Result<Value, RpcError> {
+    serde_json::to_value(value).map_err(|err| RpcError::new(TASK_ERROR, err.to_string()))
+}
+
+fn error_response(id: Value, err: RpcError) -> Value {
+    json!({ "id": id, "error": { "code": err.code, "message": err.message } })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(input: &[u8], read_only: bool) -> (TasksManager, Vec<Value>) {
+        let mut tasks_manager = TasksManager::new();
+        let mut output = vec![];
+        run(
+            &mut tasks_manager,
+            "unused.json",
+            read_only,
+            input,
+            &mut output,
+        )
+        .unwrap();
+        let responses = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        (tasks_manager, responses)
+    }
+
+    #[test]
+    fn answers_each_request_in_order() {
+        let input =
+            br##"{"id": 1, "method": "add_task", "params": {"name": "milk", "priority": "high"}}
+{"id": 2, "method": "add_task", "params": {"name": "bread"}}
+
+{"id": 3, "method": "find_task", "params": {"name": "#2"}}
+{"id": 4, "method": "remove_task", "params": {"name": "milk"}}
+{"id": 5, "method": "list_tasks"}
+{"id": 9, "method": "search_tasks", "params": {"query": "READ"}}
+{"id": 6, "method": "add_task", "params": {"name": "bread"}}
+{"id": 7, "method": "fly"}
+{"id": 8, "method": "find_task", "params": {}}
+{"method": 8}
+"##;
+        let (tasks_manager, responses) = session(input, false);
+
+        assert_eq!(responses.len(), 10);
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(responses[0]["result"]["name"], "milk");
+        assert_eq!(responses[0]["result"]["priority"], "High");
+        assert_eq!(responses[2]["result"]["name"], "bread");
+        assert_eq!(
+            responses[3]["result"]["message"],
+            "Task \"milk\" removed successfully"
+        );
+        assert_eq!(responses[4]["result"].as_array().unwrap().len(), 1);
+        assert_eq!(responses[5]["result"][0]["name"], "bread");
+        assert_eq!(responses[6]["error"]["code"], TASK_ERROR);
+        assert_eq!(responses[7]["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(responses[8]["error"]["code"], INVALID_PARAMS);
+        assert_eq!(responses[9]["error"]["code"], INVALID_REQUEST);
+        assert_eq!(tasks_manager.tasks.len(), 1);
+    }
+
+    #[test]
+    fn edit_changes_only_the_fields_sent() {
+        let input = br#"{"id": 1, "method": "add_task", "params": {"name": "milk", "priority": "high", "due_date": "2030-01-02", "tags": ["Shop"]}}
+{"id": 2, "method": "edit_task", "params": {"name": "milk", "task": {"description": "oat"}}}
+{"id": 3, "method": "edit_task", "params": {"name": "milk", "task": {"name": "oat milk", "due_date": ""}}}
+{"id": 4, "method": "edit_task", "params": {"name": "oat milk", "task": {"name": " "}}}
+"#;
+        let (tasks_manager, responses) = session(input, false);
+
+        assert_eq!(
+            responses[1]["result"]["message"],
+            "Task \"milk\" updated successfully"
+        );
+        assert_eq!(responses[3]["error"]["code"], INVALID_PARAMS);
+        let task = &tasks_manager.tasks[0];
+        assert_eq!(task.name, "oat milk");
+        assert_eq!(task.description, "oat");
+        assert!(task.priority == crate::Priority::High);
+        assert_eq!(task.due_date, None);
+        assert_eq!(task.tags, ["shop"]);
+    }
+
+    #[test]
+    fn reports_bad_lines_and_carries_on() {
+        let mut input =
+            b"{\"id\": 1, \"method\": \"add_task\", \"params\": {\"name\": \"a\"}}\r\n".to_vec();
+        input.extend_from_slice(b"not json\n");
+        input.extend_from_slice(b"{\"id\": 2, \"name\": \"\xff\xfe\"}\n");
+        input.extend_from_slice(b"{\"id\": 3, \"method\": \"list_tasks\"}");
+        let (_, responses) = session(&input, false);
+
+        assert_eq!(responses.len(), 4);
+        assert_eq!(responses[0]["result"]["name"], "a");
+        assert_eq!(responses[1]["error"]["code"], PARSE_ERROR);
+        assert_eq!(responses[2]["id"], Value::Null);
+        assert_eq!(responses[2]["error"]["code"], PARSE_ERROR);
+        assert_eq!(responses[3]["id"], 3);
+        assert_eq!(responses[3]["result"].as_array().unwrap().len(), 1);
+    }
+}
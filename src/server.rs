@@ -0,0 +1,366 @@
+use tiny_http::{Header, Method, Response, Server};
+
+use crate::{Priority, SaveMode, Task, TaskPayload, TasksManager};
+
+struct ApiResponse {
+    status: u16,
+    body: String,
+    mutated: bool,
+}
+
+impl ApiResponse {
+    fn json(status: u16, body: String) -> Self {
+        Self {
+            status,
+            body,
+            mutated: false,
+        }
+    }
+
+    fn error(status: u16, msg: &str) -> Self {
+        Self::json(status, serde_json::json!({ "error": msg }).to_string())
+    }
+
+    fn mutated(mut self) -> Self {
+        self.mutated = true;
+        self
+    }
+}
+
+pub fn serve(
+    mut tasks_manager: TasksManager,
+    filename: &str,
+    port: u16,
+    read_only: bool,
+) -> Result<(), String> {
+    let server = Server::http(("127.0.0.1", port))
+        .map_err(|err| format!("Error starting server on port {}: {}", port, err))?;
+    println!(
+        "Serving tasks from \"{}\" on http://127.0.0.1:{}",
+        filename, port
+    );
+
+    let mut backed_up = false;
+    for mut request in server.incoming_requests() {
+        let mut body = String::new();
+        let mut response = match request.as_reader().read_to_string(&mut body) {
+            Ok(_) if read_only && *request.method() != Method::Get => {
+                ApiResponse::error(403, "Server is in read-only mode")
+            }
+            Ok(_) => handle(&mut tasks_manager, request.method(), request.url(), &body),
+            Err(_) => ApiResponse::error(400, "Request body is not valid UTF-8"),
+        };
+
+        if response.mutated {
+            if let Err(msg) = persist(&mut tasks_manager, filename, &mut backed_up) {
+                eprintln!("{}", msg);
+                response = unsaved(&msg);
+            }
+        }
+
+        let header = Header::from_bytes("Content-Type", "application/json").unwrap();
+        let reply = Response::from_string(response.body)
+            .with_status_code(response.status)
+            .with_header(header);
+        if let Err(err) = request.respond(reply) {
+            eprintln!("Error sending response {}", err);
+        }
+    }
+
+    Ok(())
+}
+
+/// Saves a change to `filename`, backing the file up only on the session's first save
+/// so a busy server doesn't prune away the backups from before it started.
+fn persist(
+    tasks_manager: &mut TasksManager,
+    filename: &str,
+    backed_up: &mut bool,
+) -> Result<String, String> {
+    if *backed_up {
+        return tasks_manager.store_without_backup(filename);
+    }
+    let msg = tasks_manager.store_to_file(filename, SaveMode::Overwrite)?;
+    *backed_up = true;
+    Ok(msg)
+}
+
+/// Reports a change that was made but couldn't be written, so clients don't take it as saved.
+fn unsaved(msg: &str) -> ApiResponse {
+    ApiResponse::error(500, &format!("Change was not saved: {}", msg))
+}
+
+fn handle(tasks_manager: &mut TasksManager, method: &Method, url: &str, body: &str) -> ApiResponse {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    match (method, segments.as_slice()) {
+        (Method::Get, ["tasks"]) => list_tasks(tasks_manager, query),
+        (Method::Get, ["tasks", name]) => get_task(tasks_manager, &percent_decode(name)),
+        (Method::Post, ["tasks"]) => add_task(tasks_manager, body),
+        (Method::Delete, ["tasks", name]) => remove_task(tasks_manager, &percent_decode(name)),
+        (_, ["tasks"]) | (_, ["tasks", _]) => ApiResponse::error(405, "Method not allowed"),
+        _ => ApiResponse::error(404, "Not found"),
+    }
+}
+
+fn list_tasks(tasks_manager: &TasksManager, query: &str) -> ApiResponse {
+    let mut priority = None;
+    let mut search = None;
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        match key {
+            "priority" => match query_decode(value).parse::<Priority>() {
+                Ok(value) => priority = Some(value),
+                Err(msg) => return ApiResponse::error(400, &msg),
+            },
+            "search" => search = Some(query_decode(value).to_lowercase()),
+            _ => return ApiResponse::error(400, &format!("Unknown query parameter \"{}\"", key)),
+        }
+    }
+
+    let tasks: Vec<&Task> = tasks_manager
+        .tasks
+        .iter()
+        .filter(|task| match &priority {
+            Some(priority) => task.priority == *priority,
+            None => true,
+        })
+        .filter(|task| match &search {
+            Some(search) => {
+                task.name.to_lowercase().contains(search)
+                    || task.description.to_lowercase().contains(search)
+            }
+            None => true,
+        })
+        .collect();
+
+    to_json(200, &tasks)
+}
+
+fn get_task(tasks_manager: &TasksManager, name: &str) -> ApiResponse {
+    match tasks_manager.lookup(name) {
+        Some(index) => to_json(200, &tasks_manager.tasks[index]),
+        None => ApiResponse::error(404, &format!("Task with name \"{}\" doesn't exist", name)),
+    }
+}
+
+fn add_task(tasks_manager: &mut TasksManager, body: &str) -> ApiResponse {
+    let payload: TaskPayload = match serde_json::from_str(body) {
+        Ok(payload) => payload,
+        Err(err) => return ApiResponse::error(400, &format!("Invalid task payload: {}", err)),
+    };
+    let task = match payload.into_task() {
+        Ok(task) => task,
+        Err(msg) => return ApiResponse::error(400, &msg),
+    };
+    match tasks_manager.add_task(task) {
+        Ok(_) => to_json(201, &tasks_manager.tasks.last()).mutated(),
+        Err(msg) => ApiResponse::error(409, &msg),
+    }
+}
+
+fn remove_task(tasks_manager: &mut TasksManager, name: &str) -> ApiResponse {
+    match tasks_manager.remove_task(name) {
+        Ok(msg) => {
+            ApiResponse::json(200, serde_json::json!({ "message": msg }).to_string()).mutated()
+        }
+        Err(msg) => ApiResponse::error(404, &msg),
+    }
+}
+
+fn to_json<T: serde::Serialize + ?Sized>(status: u16, value: &T) -> ApiResponse {
+    match serde_json::to_string(value) {
+        Ok(body) => ApiResponse::json(status, body),
+        Err(err) => ApiResponse::error(500, &format!("Error serializing response {}", err)),
+    }
+}
+
+/// Decodes a query string value, where "+" stands for a space.
+fn query_decode(value: &str) -> String {
+    percent_decode(&value.replace('+', " "))
+}
+
+/// Decodes `%XX` escapes; a "+" is kept, as path segments don't use it for spaces.
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+                match u8::from_str_radix(hex, 16) {
+                    Ok(byte) => {
+                        decoded.push(byte);
+                        i += 3;
+                        continue;
+                    }
+                    Err(_) => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager_with(names: &[&str]) -> TasksManager {
+        let mut tasks_manager = TasksManager::new();
+        for name in names {
+            let task = Task::new(name.to_string(), String::new(), Priority::Low);
+            tasks_manager.add_task(task).unwrap();
+        }
+        tasks_manager
+    }
+
+    fn body(response: &ApiResponse) -> serde_json::Value {
+        serde_json::from_str(&response.body).unwrap()
+    }
+
+    #[test]
+    fn lists_and_gets_tasks() {
+        let mut tasks_manager = manager_with(&["milk", "bread"]);
+
+        let response = handle(&mut tasks_manager, &Method::Get, "/tasks", "");
+        assert_eq!(response.status, 200);
+        assert_eq!(body(&response).as_array().unwrap().len(), 2);
+        assert!(!response.mutated);
+
+        let response = handle(&mut tasks_manager, &Method::Get, "/tasks?search=BRE", "");
+        assert_eq!(body(&response)[0]["name"], "bread");
+
+        let response = handle(&mut tasks_manager, &Method::Get, "/tasks/%232", "");
+        assert_eq!(response.status, 200);
+        assert_eq!(body(&response)["name"], "bread");
+    }
+
+    #[test]
+    fn adds_a_task() {
+        let mut tasks_manager = manager_with(&[]);
+        let response = handle(
+            &mut tasks_manager,
+            &Method::Post,
+            "/tasks",
+            r#"{"name": "milk", "priority": "high"}"#,
+        );
+        assert_eq!(response.status, 201);
+        assert!(response.mutated);
+        assert_eq!(body(&response)["id"], 1);
+        assert!(tasks_manager.tasks[0].priority == Priority::High);
+    }
+
+    #[test]
+    fn rejects_bad_requests() {
+        let mut tasks_manager = manager_with(&["milk"]);
+
+        let response = handle(&mut tasks_manager, &Method::Post, "/tasks", "{");
+        assert_eq!(response.status, 400);
+        let response = handle(
+            &mut tasks_manager,
+            &Method::Post,
+            "/tasks",
+            r#"{"name": " "}"#,
+        );
+        assert_eq!(response.status, 400);
+        let response = handle(
+            &mut tasks_manager,
+            &Method::Get,
+            "/tasks?priority=urgent",
+            "",
+        );
+        assert_eq!(response.status, 400);
+
+        let response = handle(&mut tasks_manager, &Method::Get, "/tasks/bread", "");
+        assert_eq!(response.status, 404);
+        let response = handle(&mut tasks_manager, &Method::Delete, "/tasks/bread", "");
+        assert_eq!(response.status, 404);
+        let response = handle(&mut tasks_manager, &Method::Get, "/lists", "");
+        assert_eq!(response.status, 404);
+
+        let response = handle(&mut tasks_manager, &Method::Put, "/tasks/milk", "");
+        assert_eq!(response.status, 405);
+
+        let response = handle(
+            &mut tasks_manager,
+            &Method::Post,
+            "/tasks",
+            r#"{"name": "milk"}"#,
+        );
+        assert_eq!(response.status, 409);
+        assert!(!response.mutated);
+        assert_eq!(tasks_manager.tasks.len(), 1);
+    }
+
+    #[test]
+    fn backs_up_only_on_the_first_save() {
+        let dir = std::env::temp_dir().join(format!("todocli-serve-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let filename = dir.join("tasks.json").to_string_lossy().into_owned();
+        std::fs::write(&filename, "[]").unwrap();
+
+        let mut tasks_manager = manager_with(&[]);
+        let mut backed_up = false;
+        for name in ["a", "b", "c", "d"] {
+            let body = format!(r#"{{"name": "{}"}}"#, name);
+            handle(&mut tasks_manager, &Method::Post, "/tasks", &body);
+            persist(&mut tasks_manager, &filename, &mut backed_up).unwrap();
+        }
+
+        assert_eq!(crate::backup::list_backups(&filename).unwrap().len(), 1);
+        let saved = std::fs::read_to_string(&filename).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(saved.contains("\"d\""));
+    }
+
+    #[test]
+    fn failed_saves_are_reported() {
+        let mut tasks_manager = manager_with(&[]);
+        let mut backed_up = false;
+        let response = handle(
+            &mut tasks_manager,
+            &Method::Post,
+            "/tasks",
+            r#"{"name": "a"}"#,
+        );
+        assert!(response.mutated);
+
+        let msg = persist(
+            &mut tasks_manager,
+            "/nonexistent/dir/tasks.json",
+            &mut backed_up,
+        )
+        .unwrap_err();
+        let response = unsaved(&msg);
+        assert_eq!(response.status, 500);
+        assert!(body(&response)["error"]
+            .as_str()
+            .unwrap()
+            .starts_with("Change was not saved: "));
+        assert!(!backed_up);
+    }
+
+    #[test]
+    fn keeps_plus_signs_in_paths() {
+        let mut tasks_manager = manager_with(&["C++", "write docs"]);
+
+        let response = handle(&mut tasks_manager, &Method::Delete, "/tasks/C++", "");
+        assert_eq!(response.status, 200);
+        assert!(response.mutated);
+        let response = handle(&mut tasks_manager, &Method::Get, "/tasks/write%20docs", "");
+        assert_eq!(response.status, 200);
+        let response = handle(
+            &mut tasks_manager,
+            &Method::Get,
+            "/tasks?search=write+docs",
+            "",
+        );
+        assert_eq!(body(&response).as_array().unwrap().len(), 1);
+    }
+}
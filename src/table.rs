@@ -0,0 +1,232 @@
+use std::io::{IsTerminal, Write};
+
+use chrono::{DateTime, Local};
+use crossterm::style::{Color, Stylize};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::{Priority, Task};
+
+const SEPARATOR: &str = "  ";
+
+/// Descriptions get at least this many columns, even past the terminal edge.
+const MIN_DESCRIPTION_WIDTH: usize = 10;
+
+const HEADERS: [&str; 8] = [
+    "ID",
+    "Name",
+    "Priority",
+    "Status",
+    "Added",
+    "Due",
+    "Tags",
+    "Description",
+];
+
+/// How output should look on the current stdout.
+pub struct Styling {
+    pub color: bool,
+    /// Terminal width to fit rows into; `None` leaves them whole.
+    pub width: Option<usize>,
+}
+
+impl Styling {
+    /// Colors and fits to the terminal only when stdout is one; `NO_COLOR` turns color off.
+    pub fn for_stdout() -> Self {
+        if !std::io::stdout().is_terminal() {
+            return Self {
+                color: false,
+                width: None,
+            };
+        }
+
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        Self {
+            color: !no_color,
+            width: crossterm::terminal::size()
+                .ok()
+                .map(|(columns, _)| columns as usize),
+        }
+    }
+
+    pub fn paint(&self, text: &str, color: Option<Color>) -> String {
+        match color {
+            Some(color) if self.color => text.with(color).to_string(),
+            _ => text.to_owned(),
+        }
+    }
+}
+
+pub fn priority_color(priority: &Priority) -> Option<Color> {
+    match priority {
+        Priority::High => Some(Color::Red),
+        Priority::Medium => Some(Color::Yellow),
+        Priority::Low => Some(Color::Green),
+    }
+}
+
+pub fn format_time(time: DateTime<Local>) -> String {
+    time.format("%d-%m-%Y %H:%M").to_string()
+}
+
+/// The status as shown, with the completion time once the task is done.
+pub fn status_label(task: &Task) -> String {
+    match task.completed_at {
+        Some(completed_at) => format!("{} {}", task.status, format_time(completed_at)),
+        None => task.status.to_string(),
+    }
+}
+
+/// The due date as shown, marked and colored when the task is overdue.
+pub fn due_label(task: &Task, now: DateTime<Local>) -> (String, Option<Color>) {
+    match task.due_date {
+        Some(due_date) if task.is_overdue(now) => (
+            format!("{} OVERDUE", format_time(due_date)),
+            Some(Color::Red),
+        ),
+        Some(due_date) => (format_time(due_date), None),
+        None => (String::new(), None),
+    }
+}
+
+/// Terminal columns taken by `text`; wide characters such as emoji count as two.
+pub fn width(text: &str) -> usize {
+    UnicodeWidthStr::width(text)
+}
+
+/// Cuts `text` to at most `max` columns, ending with "…" when shortened.
+pub fn truncate(text: &str, max: usize) -> String {
+    if width(text) <= max {
+        return text.to_owned();
+    }
+    if max == 0 {
+        return String::new();
+    }
+
+    let mut truncated = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if used + char_width + 1 > max {
+            break;
+        }
+        truncated.push(c);
+        used += char_width;
+    }
+    truncated.push('…');
+    truncated
+}
+
+fn pad(text: &str, column_width: usize) -> String {
+    format!(
+        "{}{}",
+        text,
+        " ".repeat(column_width.saturating_sub(width(text)))
+    )
+}
+
+/// Renders the tasks as a table sized to their contents, one line per task.
+///
+/// Only the description, in the last column, is shortened to fit the styling's width.
+pub fn task_table(tasks: &[&Task], styling: &Styling, now: DateTime<Local>) -> String {
+    let rows: Vec<[(String, Option<Color>); 8]> = tasks
+        .iter()
+        .map(|task| {
+            [
+                (format!("#{}", task.id), None),
+                (single_line(&task.name), None),
+                (task.priority.to_string(), priority_color(&task.priority)),
+                (status_label(task), None),
+                (format_time(task.add_time), None),
+                due_label(task, now),
+                (task.tags.join(","), None),
+                (single_line(&task.description), None),
+            ]
+        })
+        .collect();
+
+    let mut widths = HEADERS.map(width);
+    for row in &rows {
+        for (column, (text, _)) in row.iter().enumerate() {
+            widths[column] = widths[column].max(width(text));
+        }
+    }
+    let last = HEADERS.len() - 1;
+    let fixed: usize = widths[..last].iter().sum::<usize>() + SEPARATOR.len() * last;
+    if let Some(total) = styling.width {
+        widths[last] = total.saturating_sub(fixed).max(MIN_DESCRIPTION_WIDTH);
+    }
+
+    let mut table = String::new();
+    let header: Vec<String> = HEADERS
+        .iter()
+        .zip(widths)
+        .map(|(header, column_width)| pad(header, column_width))
+        .collect();
+    table.push_str(header.join(SEPARATOR).trim_end());
+    table.push('\n');
+    let rule: Vec<String> = widths
+        .iter()
+        .map(|&column_width| "-".repeat(column_width))
+        .collect();
+    table.push_str(&rule.join(SEPARATOR));
+    table.push('\n');
+
+    for row in rows {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .enumerate()
+            .map(|(column, ((text, color), column_width))| {
+                // The last column isn't padded, so lines carry no trailing spaces.
+                let text = if column == last {
+                    truncate(text, column_width)
+                } else {
+                    pad(text, column_width)
+                };
+                styling.paint(&text, *color)
+            })
+            .collect();
+        table.push_str(cells.join(SEPARATOR).trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+/// Prints the tasks as a table styled for stdout.
+pub fn print(tasks: &[&Task]) {
+    if tasks.is_empty() {
+        println!("No tasks");
+        return;
+    }
+
+    let table = task_table(tasks, &Styling::for_stdout(), Local::now());
+    let mut out = std::io::stdout().lock();
+    let _ = out.write_all(table.as_bytes()).and_then(|_| out.flush());
+}
+
+pub fn single_line(text: &str) -> String {
+    text.lines().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::Status;
+
+    #[test]
+    fn status_column_shows_the_completion_time() {
+        let mut task = Task::new("milk".to_owned(), String::new(), Priority::Low);
+        task.status = Status::Done;
+        task.completed_at = Some(Local.with_ymd_and_hms(2024, 3, 5, 14, 30, 0).unwrap());
+        let styling = Styling {
+            color: false,
+            width: None,
+        };
+
+        let table = task_table(&[&task], &styling, Local::now());
+        let row = table.lines().nth(2).unwrap();
+        assert!(row.contains("Done 05-03-2024 14:30"), "{}", row);
+    }
+}
@@ -0,0 +1,150 @@
+use std::time::Duration;
+
+use serde_json::json;
+
+use crate::{Task, TaskEvent};
+
+/// Environment variable holding the webhook URL when `--webhook` isn't given.
+pub const URL_VAR: &str = "TODO_WEBHOOK_URL";
+
+/// Whole-request limit, so a slow endpoint only stalls the command briefly.
+const TIMEOUT: Duration = Duration::from_secs(3);
+
+/// A Slack incoming webhook that hears about added, completed and removed tasks.
+pub struct Webhook {
+    url: String,
+    agent: ureq::Agent,
+}
+
+impl Webhook {
+    pub fn new(url: String) -> Self {
+        let agent = ureq::Agent::config_builder()
+            .timeout_global(Some(TIMEOUT))
+            .build()
+            .into();
+        Self { url, agent }
+    }
+
+    /// Picks the URL: `--webhook` flag, then the environment; `None` when neither is set.
+    pub fn resolve(flag: Option<String>) -> Option<Self> {
+        let url = flag.or_else(|| std::env::var(URL_VAR).ok())?;
+        if url.trim().is_empty() {
+            return None;
+        }
+        Some(Self::new(url))
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn notify(&self, event: TaskEvent, task: &Task) -> Result<(), String> {
+        self.post(&event_text(event, task))
+    }
+
+    /// Sends a test message so the URL can be checked without touching any task.
+    pub fn ping(&self) -> Result<String, String> {
+        self.post("Ping from todocli: the webhook is set up")?;
+        Ok(format!("Webhook \"{}\" answered the ping", self.url))
+    }
+
+    fn post(&self, text: &str) -> Result<(), String> {
+        self.agent
+            .post(&self.url)
+            .header("Content-Type", "application/json")
+            .send(payload(text))
+            .map(|_| ())
+            .map_err(|err| format!("Error posting to webhook: {}", err))
+    }
+}
+
+fn event_text(event: TaskEvent, task: &Task) -> String {
+    format!(
+        "Task \"{}\" {} ({} priority)",
+        escape(&task.name),
+        event,
+        task.priority
+    )
+}
+
+/// The incoming-webhook body: just the message text.
+fn payload(text: &str) -> String {
+    json!({ "text": text }).to_string()
+}
+
+/// Escapes the characters Slack reads as markup, so a name can't form a link or mention.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::Priority;
+
+    #[test]
+    fn text_names_the_task_action_and_priority() {
+        let task = Task::new("milk".to_owned(), String::new(), Priority::High);
+        assert_eq!(
+            event_text(TaskEvent::Completed, &task),
+            "Task \"milk\" completed (High priority)"
+        );
+    }
+
+    #[test]
+    fn names_cannot_inject_slack_markup() {
+        let task = Task::new("<!channel> & co".to_owned(), String::new(), Priority::Low);
+        assert_eq!(
+            event_text(TaskEvent::Added, &task),
+            "Task \"&lt;!channel&gt; &amp; co\" added (Low priority)"
+        );
+    }
+
+    #[test]
+    fn payload_is_slack_json() {
+        let value: serde_json::Value = serde_json::from_str(&payload("say \"hi\"")).unwrap();
+        assert_eq!(value, json!({ "text": "say \"hi\"" }));
+    }
+
+    #[test]
+    fn notify_posts_the_payload() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", server.server_addr().to_ip().unwrap());
+        let received = thread::spawn(move || {
+            let mut request = server.recv().unwrap();
+            let mut body = String::new();
+            request.as_reader().read_to_string(&mut body).unwrap();
+            let method = request.method().to_string();
+            request
+                .respond(tiny_http::Response::from_string("ok"))
+                .unwrap();
+            (method, body)
+        });
+
+        let task = Task::new("milk".to_owned(), String::new(), Priority::Medium);
+        Webhook::new(url).notify(TaskEvent::Removed, &task).unwrap();
+
+        let (method, body) = received.join().unwrap();
+        assert_eq!(method, "POST");
+        assert_eq!(body, payload("Task \"milk\" removed (Medium priority)"));
+    }
+
+    #[test]
+    fn error_statuses_are_reported() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", server.server_addr().to_ip().unwrap());
+        let handle = thread::spawn(move || {
+            let request = server.recv().unwrap();
+            request
+                .respond(tiny_http::Response::from_string("no").with_status_code(404))
+                .unwrap();
+        });
+
+        assert!(Webhook::new(url).ping().is_err());
+        handle.join().unwrap();
+    }
+}